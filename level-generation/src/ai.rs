@@ -1,4 +1,4 @@
-/* 
+/*
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 enum FieldKnowledge {
   Unknown,
@@ -9,9 +9,6 @@ enum FieldKnowledge {
 
 */
 
-
-
-
 /*use crate::board::{Board, BoardVec};
 use crate::{Field, Game};
 
@@ -94,4 +91,4 @@ impl From<&Game> for State {
     result
   }
 }
-*/
\ No newline at end of file
+*/
@@ -0,0 +1,140 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+
+use clap::Parser;
+use snake::board::BoardVec;
+use snake::serialize::LevelData;
+use snake::session::{Flag, GameSession, Mark};
+
+const HELP: &str = "\
+Commands, several can be given on one line (e.g. \"ddx\"):
+  w a s d  move the cursor
+  x        mark snake
+  e        mark empty
+  c        clear the mark
+  u r      undo, redo
+  h        show a hint and move the cursor there
+  k        check the marks for mistakes
+  ?        show this help
+  q        quit";
+
+#[derive(Parser)]
+#[command(about = "Play a snake level in the terminal")]
+struct Cli {
+  /// Level file to play
+  level: PathBuf,
+}
+
+// The frame of the Debug output of `State`, but with the `Display` symbols of the fields throughout: unknown fields
+// stay blank instead of showing their union number. The cursor is drawn in inverse video.
+fn render(session: &GameSession, cursor: BoardVec) -> String {
+  let mut out = format!("x{}x\n", "-".repeat(session.width() as usize));
+  for y in 0..session.height() {
+    out.push('|');
+    for x in 0..session.width() {
+      let pos = BoardVec::new(x as i32, y as i32);
+      let field = session.field(pos);
+      if pos == cursor {
+        out.push_str(&format!("\x1b[7m{field}\x1b[0m"));
+      } else {
+        out.push_str(&field.to_string());
+      }
+    }
+    out.push_str("|\n");
+  }
+  out.push_str(&format!("x{}x", "-".repeat(session.width() as usize)));
+  out
+}
+
+fn mark(session: &mut GameSession, cursor: BoardVec, mark: Mark) {
+  if let Err(err) = session.mark(cursor, mark) {
+    println!("{err}");
+  }
+}
+
+fn check(session: &GameSession) {
+  let flags = session.flags();
+  if flags.is_empty() {
+    println!("No mistakes so far");
+  }
+  for (pos, flag) in flags {
+    match flag {
+      Flag::Rule(violation) => println!("{pos:?}: {violation}"),
+      Flag::Solution => println!("{pos:?} is wrong"),
+    }
+  }
+}
+
+fn main() {
+  let cli = Cli::parse();
+  let session = LevelData::load(&cli.level).and_then(|data| GameSession::from_level(&data));
+  let mut session = match session {
+    Ok(session) => session,
+    Err(err) => {
+      eprintln!("{}: {err}", cli.level.display());
+      process::exit(1);
+    }
+  };
+
+  println!("{HELP}");
+  let mut cursor = BoardVec::new(0, 0);
+  let stdin = io::stdin();
+  loop {
+    println!("{}", render(&session, cursor));
+    print!("{:?}> ", cursor);
+    io::stdout().flush().unwrap();
+
+    let mut line = String::new();
+    if stdin.lock().read_line(&mut line).unwrap() == 0 {
+      return;
+    }
+
+    for c in line.trim().chars() {
+      let step = match c {
+        'w' => BoardVec::new(0, -1),
+        'a' => BoardVec::new(-1, 0),
+        's' => BoardVec::new(0, 1),
+        'd' => BoardVec::new(1, 0),
+        _ => BoardVec::new(0, 0),
+      };
+      if session.contains(cursor + step) {
+        cursor = cursor + step;
+      }
+
+      match c {
+        'w' | 'a' | 's' | 'd' => {}
+        'x' => mark(&mut session, cursor, Mark::Snake),
+        'e' => mark(&mut session, cursor, Mark::Empty),
+        'c' => mark(&mut session, cursor, Mark::Clear),
+        'u' => {
+          if session.undo().is_none() {
+            println!("Nothing to undo");
+          }
+        }
+        'r' => {
+          if session.redo().is_none() {
+            println!("Nothing to redo");
+          }
+        }
+        'h' => match session.hint() {
+          Some(hint) => {
+            cursor = hint.pos;
+            println!("{hint}");
+          }
+          None => println!("No hint, every field follows from your correct marks"),
+        },
+        'k' => check(&session),
+        '?' => println!("{HELP}"),
+        'q' => return,
+        _ => println!("Unknown command '{c}', type ? for help"),
+      }
+    }
+
+    if session.is_solved() {
+      println!("{}", render(&session, BoardVec::new(-1, -1)));
+      println!("Solved! The snake connects both ends.");
+      return;
+    }
+  }
+}
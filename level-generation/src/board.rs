@@ -40,6 +40,15 @@ impl Serialize for BoardVec {
   }
 }
 
+impl<'de> Deserialize<'de> for BoardVec {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let [x, y] = <[i32; 2]>::deserialize(deserializer)?;
+    Ok(BoardVec::new(x, y))
+  }
+}
 
 impl BoardVec {
   pub const fn new(x: i32, y: i32) -> BoardVec {
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use crate::serialize::{LevelData, LevelError};
+use crate::svg::{render_board, SvgOptions};
+use crate::{EmptyPolicy, State};
+
+// A4 at 96 dpi
+const PAGE_WIDTH: f64 = 794.0;
+const PAGE_HEIGHT: f64 = 1123.0;
+const MARGIN: f64 = 48.0;
+const HEADER: f64 = 64.0;
+const FONT_SIZE: f64 = 13.0;
+
+pub const RULES: &str = "Connect the two large dots with a snake that runs horizontally and vertically through the \
+                         grid. The snake never touches itself side by side. Squares are snake fields, small dots are \
+                         empty fields.";
+
+#[derive(Debug, Clone)]
+pub struct BookOptions {
+  pub title: String,
+  // puzzles per row and rows per page
+  pub columns: u32,
+  pub rows: u32,
+}
+
+impl Default for BookOptions {
+  fn default() -> Self {
+    Self {
+      title: "Snake puzzles".to_string(),
+      columns: 2,
+      rows: 3,
+    }
+  }
+}
+
+// What the player needs to know about the empty regions of a level
+pub fn policy_rule(policy: &EmptyPolicy) -> String {
+  match policy {
+    EmptyPolicy::None => "Empty regions may have any size.".to_string(),
+    EmptyPolicy::Fix(n) => format!("Every empty region has exactly {n} fields."),
+    EmptyPolicy::Ascending(..) => {
+      "The empty regions have the sizes 1, 2, 3 and so on, each exactly once.".to_string()
+    }
+    EmptyPolicy::Range(min, max) => format!("Every empty region has {min} to {max} fields."),
+    EmptyPolicy::Sizes(sizes, _) => {
+      let sizes: Vec<String> = sizes.iter().map(|size| size.to_string()).collect();
+      format!("The empty regions have exactly the sizes {}.", sizes.join(", "))
+    }
+    EmptyPolicy::Distinct(_) => "No two empty regions have the same size.".to_string(),
+    EmptyPolicy::Odd => "Every empty region has an odd number of fields.".to_string(),
+    EmptyPolicy::Even => "Every empty region has an even number of fields.".to_string(),
+  }
+}
+
+// Lays out the levels as A4 pages, the puzzles first and their solutions in the same order at the back.
+// Every page is a standalone SVG document.
+pub fn render_book(levels: &[LevelData], options: &BookOptions) -> Result<Vec<String>, LevelError> {
+  let boards: Vec<(State, State)> = levels.iter().map(LevelData::states).collect::<Result<_, _>>()?;
+  let per_page = (options.columns * options.rows).max(1) as usize;
+
+  let mut pages = Vec::new();
+  for solutions in [false, true] {
+    for (page, chunk) in boards.chunks(per_page).enumerate() {
+      let first = page * per_page;
+      let heading = match (solutions, page) {
+        (false, 0) => options.title.clone(),
+        (false, _) => format!("{} (continued)", options.title),
+        (true, _) => "Solutions".to_string(),
+      };
+      pages.push(render_page(
+        &heading,
+        first,
+        chunk,
+        solutions,
+        options,
+        page == 0 && !solutions,
+      ));
+    }
+  }
+
+  Ok(pages)
+}
+
+fn render_page(
+  heading: &str,
+  first: usize,
+  boards: &[(State, State)],
+  solutions: bool,
+  options: &BookOptions,
+  with_rules: bool,
+) -> String {
+  let mut out = String::new();
+  writeln!(
+    out,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}" viewBox="0 0 {PAGE_WIDTH} {PAGE_HEIGHT}">"#
+  )
+  .unwrap();
+  writeln!(
+    out,
+    r#"<rect width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}" fill="white"/>"#
+  )
+  .unwrap();
+  writeln!(
+    out,
+    r#"<text x="{MARGIN}" y="{}" font-family="sans-serif" font-size="24" font-weight="bold">{}</text>"#,
+    MARGIN + 16.0,
+    escape(heading)
+  )
+  .unwrap();
+
+  let mut top = MARGIN + HEADER;
+  if with_rules {
+    for line in wrap(RULES, 110) {
+      text(&mut out, MARGIN, top, &line);
+      top += FONT_SIZE * 1.4;
+    }
+    top += FONT_SIZE;
+  }
+
+  let slot_width = (PAGE_WIDTH - 2.0 * MARGIN) / options.columns.max(1) as f64;
+  let slot_height = (PAGE_HEIGHT - MARGIN - top) / options.rows.max(1) as f64;
+  for (i, (puzzle, solution)) in boards.iter().enumerate() {
+    let (column, row) = (i as u32 % options.columns.max(1), i as u32 / options.columns.max(1));
+    let (x, y) = (MARGIN + column as f64 * slot_width, top + row as f64 * slot_height);
+
+    // number and rule above the board, the rule is left out for solutions
+    let mut lines = vec![format!("{}.", first + i + 1)];
+    if !solutions {
+      lines.extend(wrap(
+        &policy_rule(puzzle.empty_policy()),
+        (slot_width / (FONT_SIZE * 0.55)) as usize,
+      ));
+    }
+    for (j, line) in lines.iter().enumerate() {
+      text(&mut out, x, y + FONT_SIZE * (1.0 + j as f64 * 1.4), line);
+    }
+
+    let board_top = y + FONT_SIZE * (lines.len() as f64 * 1.4 + 0.6);
+    let cell = ((slot_width - FONT_SIZE * 2.0) / puzzle.width() as f64)
+      .min((y + slot_height - board_top - FONT_SIZE) / puzzle.height() as f64)
+      .floor()
+      .max(4.0);
+    let options = SvgOptions {
+      cell_size: cell as u32,
+      solution: solutions,
+    };
+    render_board(&mut out, puzzle, solution, &options, x, board_top);
+  }
+
+  out.push_str("</svg>\n");
+  out
+}
+
+fn text(out: &mut String, x: f64, y: f64, text: &str) {
+  writeln!(
+    out,
+    r#"<text x="{x}" y="{y}" font-family="sans-serif" font-size="{FONT_SIZE}">{}</text>"#,
+    escape(text)
+  )
+  .unwrap();
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Breaks `text` into lines of at most `width` characters at spaces
+fn wrap(text: &str, width: usize) -> Vec<String> {
+  let mut lines: Vec<String> = Vec::new();
+  for word in text.split_whitespace() {
+    match lines.last_mut() {
+      Some(line) if line.len() + 1 + word.len() <= width => {
+        line.push(' ');
+        line.push_str(word);
+      }
+      _ => lines.push(word.to_string()),
+    }
+  }
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{render_book, wrap, BookOptions};
+  use crate::serialize::LevelData;
+  use crate::test_util::example_level;
+
+  #[test]
+  fn test_render_book() {
+    let levels: Vec<LevelData> = (0..7).map(|_| example_level()).collect();
+    let pages = render_book(&levels, &BookOptions::default()).unwrap();
+
+    // 6 puzzles per page
+    assert_eq!(pages.len(), 4);
+    assert!(pages[1].contains(">7.<"));
+    assert!(pages[0].contains("each exactly once"));
+    assert!(pages[2].contains(">Solutions<"));
+    assert!(pages[3].contains("polyline"));
+    assert!(!pages[0].contains("polyline"));
+  }
+
+  #[test]
+  fn test_wrap() {
+    assert_eq!(wrap("a bb ccc dd", 6), ["a bb", "ccc dd"]);
+    assert_eq!(wrap("", 6), Vec::<String>::new());
+  }
+}
@@ -0,0 +1,138 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::progress::{SearchObserver, SearchProgress};
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GaveUp {
+  Deadline,
+  NodeLimit,
+  Cancelled,
+}
+
+impl fmt::Display for GaveUp {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GaveUp::Deadline => write!(f, "gave up: deadline passed"),
+      GaveUp::NodeLimit => write!(f, "gave up: node limit reached"),
+      GaveUp::Cancelled => write!(f, "gave up: cancelled"),
+    }
+  }
+}
+
+impl std::error::Error for GaveUp {}
+
+// Limits the work of a search. The searches count every state they visit with `spend`; once the budget is used up
+// it stays used up, the searches return early and their entry points report `GaveUp`. A budget passed to several
+// calls is shared between them. Searches also report their progress to the budget's observer, if any.
+#[derive(Default)]
+pub struct Budget {
+  deadline: Option<Instant>,
+  max_nodes: Option<usize>,
+  cancel: Option<CancelToken>,
+  observer: Option<Arc<dyn SearchObserver>>,
+  nodes: Cell<usize>,
+  gave_up: Cell<Option<GaveUp>>,
+}
+
+impl Budget {
+  pub fn unlimited() -> Self {
+    Self::default()
+  }
+
+  pub fn with_deadline(self, deadline: Instant) -> Self {
+    Self {
+      deadline: Some(deadline),
+      ..self
+    }
+  }
+
+  pub fn with_timeout(self, timeout: Duration) -> Self {
+    self.with_deadline(Instant::now() + timeout)
+  }
+
+  pub fn with_max_nodes(self, max_nodes: usize) -> Self {
+    Self {
+      max_nodes: Some(max_nodes),
+      ..self
+    }
+  }
+
+  pub fn with_cancel(self, cancel: CancelToken) -> Self {
+    Self {
+      cancel: Some(cancel),
+      ..self
+    }
+  }
+
+  pub fn with_observer(self, observer: Arc<dyn SearchObserver>) -> Self {
+    Self {
+      observer: Some(observer),
+      ..self
+    }
+  }
+
+  pub(crate) fn report(&self, progress: &SearchProgress) {
+    if let Some(observer) = &self.observer {
+      observer.on_progress(progress);
+    }
+  }
+
+  // Counts one search node, returns false if the search should stop.
+  pub fn spend(&self) -> bool {
+    if self.gave_up.get().is_some() {
+      return false;
+    }
+
+    let nodes = self.nodes.get() + 1;
+    self.nodes.set(nodes);
+    let gave_up = if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+      Some(GaveUp::Cancelled)
+    } else if self.max_nodes.is_some_and(|max| nodes > max) {
+      Some(GaveUp::NodeLimit)
+    } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+      Some(GaveUp::Deadline)
+    } else {
+      None
+    };
+
+    self.gave_up.set(gave_up);
+    gave_up.is_none()
+  }
+
+  pub fn nodes(&self) -> usize {
+    self.nodes.get()
+  }
+
+  pub fn gave_up(&self) -> Option<GaveUp> {
+    self.gave_up.get()
+  }
+
+  // `value` if the budget lasted, results of a search that was cut short are not reliable.
+  pub fn check<T>(&self, value: T) -> Result<T, GaveUp> {
+    match self.gave_up.get() {
+      Some(gave_up) => Err(gave_up),
+      None => Ok(value),
+    }
+  }
+}
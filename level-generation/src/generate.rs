@@ -0,0 +1,782 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use std::{fs, io, thread};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::board::BoardVec;
+use crate::budget::{Budget, CancelToken, GaveUp};
+use crate::hint::explain_step;
+use crate::progress::SearchObserver;
+use crate::rating::{rate, DifficultyTarget, Rating};
+use crate::serialize::{LevelData, LevelError, MoveData};
+use crate::verify::verify_level_within;
+use crate::{
+  canonical_trace, canonical_trace_within, find_solution_trace, solve_within, EmptyPolicy, Field, SolveStep,
+  State, Symmetry,
+};
+
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+  pub width: u32,
+  pub height: u32,
+  pub empty_policy: EmptyPolicy,
+  pub max_assume_depth: usize,
+  pub symmetry: Option<Symmetry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedLevel {
+  pub initial: State,
+  pub solution: State,
+  pub initial_open: Vec<BoardVec>,
+  // deductions in the order of the canonical trace, see `canonical_trace`
+  pub steps: Vec<SolveStep>,
+  pub max_assume_depth: usize,
+  // the symmetry of the clues, kept when clues are removed
+  pub symmetry: Option<Symmetry>,
+  pub seed: Option<u64>,
+}
+
+impl GeneratedLevel {
+  pub fn from_solution(
+    initial: &State,
+    solution: &State,
+    max_assume_depth: usize,
+    symmetry: Option<Symmetry>,
+    budget: &Budget,
+  ) -> Result<Option<Self>, GaveUp> {
+    let Some(path) = find_solution_trace(initial.clone(), solution, max_assume_depth, symmetry, budget)? else {
+      return Ok(None);
+    };
+
+    Ok(Some(Self {
+      initial: initial.clone(),
+      solution: solution.clone(),
+      initial_open: path.initial_open,
+      steps: path.steps,
+      max_assume_depth,
+      symmetry,
+      seed: None,
+    }))
+  }
+
+  pub fn from_level_data(level: &LevelData) -> Result<Self, LevelError> {
+    let solution = level.solution()?;
+    let initial = State::new(
+      level.width(),
+      level.height(),
+      solution.snake_ends()[0],
+      solution.snake_ends()[1],
+      level.empty_policy()?,
+    );
+    let initial_open: Vec<BoardVec> = level
+      .initial_open()
+      .iter()
+      .copied()
+      .filter(|&pos| initial.field(pos) == Field::Unknown)
+      .collect();
+
+    let (width, height) = (level.width(), level.height());
+    let symmetry = level.symmetry();
+    if let Some(symmetry) = symmetry.filter(|&symmetry| {
+      !symmetry.fits(width, height)
+        || initial_open
+          .iter()
+          .any(|&pos| !initial_open.contains(&symmetry.image(pos, width, height)))
+    }) {
+      return Err(LevelError::AsymmetricClues(symmetry));
+    }
+
+    // the stored moves may come in any order, `verify_level` checks them as they are
+    let (_, steps) =
+      canonical_trace(&level.puzzle()?, level.max_assumption_depth()).map_err(LevelError::UndeducibleMove)?;
+    if let Some(step) = steps.iter().find(|step| step.field != solution.field(step.pos)) {
+      return Err(LevelError::UndeducibleMove(step.pos));
+    }
+
+    Ok(Self {
+      initial,
+      solution,
+      initial_open,
+      steps,
+      max_assume_depth: level.max_assumption_depth(),
+      symmetry,
+      seed: level.seed(),
+    })
+  }
+
+  // The level the clues `initial_open` give, if they can be deduced to `solution` within the assumption depth.
+  pub fn from_clues(
+    initial: &State,
+    solution: &State,
+    initial_open: Vec<BoardVec>,
+    max_assume_depth: usize,
+  ) -> Option<Self> {
+    Self::from_clues_within(initial, solution, initial_open, max_assume_depth, &Budget::unlimited())
+      .expect("an unlimited budget never runs out")
+  }
+
+  pub fn from_clues_within(
+    initial: &State,
+    solution: &State,
+    initial_open: Vec<BoardVec>,
+    max_assume_depth: usize,
+    budget: &Budget,
+  ) -> Result<Option<Self>, GaveUp> {
+    let mut puzzle = initial.clone();
+    for &pos in initial_open.iter() {
+      if puzzle.try_set(pos, solution.field(pos)).is_err() {
+        return Ok(None);
+      }
+    }
+
+    let Ok((_, steps)) = canonical_trace_within(&puzzle, max_assume_depth, budget)? else {
+      return Ok(None);
+    };
+    if steps.iter().any(|step| step.field != solution.field(step.pos)) {
+      return Ok(None);
+    }
+
+    Ok(Some(Self {
+      initial: initial.clone(),
+      solution: solution.clone(),
+      initial_open,
+      steps,
+      max_assume_depth,
+      symmetry: None,
+      seed: None,
+    }))
+  }
+
+  // The same level opened with other clues, if those still lead to a unique solution that can be deduced within
+  // the assumption depth.
+  pub fn with_clues(&self, initial_open: Vec<BoardVec>) -> Option<Self> {
+    self
+      .with_clues_within(initial_open, &Budget::unlimited())
+      .expect("an unlimited budget never runs out")
+  }
+
+  pub fn with_clues_within(&self, initial_open: Vec<BoardVec>, budget: &Budget) -> Result<Option<Self>, GaveUp> {
+    let mut puzzle = self.initial.clone();
+    for &pos in initial_open.iter() {
+      puzzle.set(pos, self.solution.field(pos));
+    }
+
+    let mut results = Vec::new();
+    solve_within(puzzle, &mut results, 2, budget)?;
+    if results.len() != 1 {
+      return Ok(None);
+    }
+
+    let level = Self::from_clues_within(
+      &self.initial,
+      &self.solution,
+      initial_open,
+      self.max_assume_depth,
+      budget,
+    )?;
+    Ok(level.map(|level| Self {
+      symmetry: self.symmetry,
+      seed: self.seed,
+      ..level
+    }))
+  }
+
+  // Groups fields into the sets that have to be opened together to keep the clues symmetric.
+  fn orbits(&self, fields: impl Iterator<Item = BoardVec>) -> Vec<Vec<BoardVec>> {
+    let (width, height) = (self.initial.width(), self.initial.height());
+    let mut orbits: Vec<Vec<BoardVec>> = Vec::new();
+    for pos in fields {
+      if !orbits.iter().any(|orbit| orbit.contains(&pos)) {
+        orbits.push(match self.symmetry {
+          Some(symmetry) => symmetry.orbit(pos, width, height),
+          None => vec![pos],
+        });
+      }
+    }
+    orbits
+  }
+
+  // Removes clues, or symmetric pairs of clues, one at a time as long as the level stays solvable. Afterwards all
+  // smaller clue sets are tried, as long as there are no more than `exhaustive_limit` of them, to tell whether the
+  // result is minimal.
+  pub fn minimize(&self, exhaustive_limit: usize) -> (Self, Minimality) {
+    self
+      .minimize_within(exhaustive_limit, &Budget::unlimited())
+      .expect("an unlimited budget never runs out")
+  }
+
+  pub fn minimize_within(&self, exhaustive_limit: usize, budget: &Budget) -> Result<(Self, Minimality), GaveUp> {
+    let mut level = self.clone();
+    let mut i = 0;
+    loop {
+      let mut orbits = level.orbits(level.initial_open.iter().copied());
+      if i >= orbits.len() {
+        break;
+      }
+      orbits.remove(i);
+      match level.with_clues_within(orbits.concat(), budget)? {
+        Some(reduced) => level = reduced,
+        None => i += 1,
+      }
+    }
+
+    let is_candidate = |pos: BoardVec| self.initial.field(pos) == Field::Unknown;
+    let candidates: Vec<Vec<BoardVec>> = self
+      .orbits(self.initial.board.positions().filter(|&pos| is_candidate(pos)))
+      .into_iter()
+      .filter(|orbit| orbit.iter().all(|&pos| is_candidate(pos)))
+      .collect();
+    let clue_orbits = level.orbits(level.initial_open.iter().copied()).len();
+    let smaller_sets = (0..clue_orbits)
+      .map(|k| binomial(candidates.len(), k))
+      .fold(0, usize::saturating_add);
+    if smaller_sets > exhaustive_limit {
+      return Ok((level, Minimality::Irreducible));
+    }
+
+    for k in 0..clue_orbits {
+      for orbits in combinations(&candidates, k) {
+        if let Some(smaller) = level.with_clues_within(orbits.concat(), budget)? {
+          return smaller.minimize_within(exhaustive_limit, budget);
+        }
+      }
+    }
+    Ok((level, Minimality::Minimal))
+  }
+
+  pub fn puzzle(&self) -> State {
+    let mut state = self.initial.clone();
+    for &pos in self.initial_open.iter() {
+      state.set(pos, self.solution.field(pos));
+    }
+    state
+  }
+
+  pub fn moves(&self) -> Vec<MoveData> {
+    let mut state = self.puzzle();
+    self
+      .steps
+      .iter()
+      .map(|step| {
+        let hint = explain_step(&state, step).expect("solve steps should always be explainable");
+        state.set(step.pos, step.field);
+        MoveData {
+          pos: step.pos,
+          field: step.field,
+          rule: hint.rule,
+        }
+      })
+      .collect()
+  }
+
+  pub fn rating(&self) -> Rating {
+    rate(&self.puzzle(), &self.steps)
+  }
+
+  pub fn level_data(&self) -> LevelData {
+    LevelData::new(
+      &self.solution,
+      self.initial_open.clone(),
+      self.moves(),
+      self.max_assume_depth,
+    )
+    .with_seed(self.seed)
+    .with_symmetry(self.symmetry)
+    .with_difficulty(Some(self.rating().difficulty()))
+  }
+
+  pub fn board_hash(&self) -> u64 {
+    let hasher = &mut DefaultHasher::new();
+    self.initial.hash(hasher);
+    hasher.finish()
+  }
+
+  pub fn file_name(&self) -> String {
+    format!(
+      "level_{}x{}_{}_{}.json",
+      self.initial.width(),
+      self.initial.height(),
+      self.max_assume_depth,
+      self.board_hash()
+    )
+  }
+
+  pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+    let serialized = serde_json::to_string_pretty(&self.level_data())?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(self.file_name());
+    fs::write(&path, serialized)?;
+    Ok(path)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Minimality {
+  // no smaller set of clues works
+  Minimal,
+  // no single clue can be removed, smaller clue sets were not searched
+  Irreducible,
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+  (0..k).fold(1usize, |acc, i| acc.saturating_mul(n - i) / (i + 1))
+}
+
+fn combinations<T: Clone>(items: &[T], k: usize) -> impl Iterator<Item = Vec<T>> + '_ {
+  let mut indices: Option<Vec<usize>> = (k <= items.len()).then(|| (0..k).collect());
+  std::iter::from_fn(move || {
+    let current = indices.clone()?;
+    let combination = current.iter().map(|&i| items[i].clone()).collect();
+
+    // advance to the next combination in lexicographic order
+    let next = indices.as_mut().unwrap();
+    match (0..k).rev().find(|&i| next[i] < items.len() - k + i) {
+      Some(i) => {
+        next[i] += 1;
+        for j in i + 1..k {
+          next[j] = next[j - 1] + 1;
+        }
+      }
+      None => indices = None,
+    }
+    Some(combination)
+  })
+}
+
+// Returns `level`, or the same board opened for a lower assumption depth, if its rating lies in `target`.
+pub fn retarget(
+  level: GeneratedLevel,
+  target: &DifficultyTarget,
+  budget: &Budget,
+) -> Result<Option<GeneratedLevel>, GaveUp> {
+  if target.matches(&level.rating()) {
+    return Ok(Some(level));
+  }
+
+  for depth in (0..level.max_assume_depth).rev() {
+    let easier = GeneratedLevel::from_solution(&level.initial, &level.solution, depth, level.symmetry, budget)?;
+    if let Some(mut easier) = easier.filter(|easier| target.matches(&easier.rating())) {
+      easier.seed = level.seed;
+      return Ok(Some(easier));
+    }
+  }
+  Ok(None)
+}
+
+pub fn try_generate(options: &GenerateOptions, seed: u64) -> Option<GeneratedLevel> {
+  try_generate_within(options, seed, &Budget::unlimited()).expect("an unlimited budget never runs out")
+}
+
+pub fn try_generate_within(
+  options: &GenerateOptions,
+  seed: u64,
+  budget: &Budget,
+) -> Result<Option<GeneratedLevel>, GaveUp> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let initial = State::new_rand(options.width, options.height, options.empty_policy.clone(), &mut rng);
+  let mut results = Vec::new();
+  solve_within(initial.clone(), &mut results, 2, budget)?;
+
+  let Some(solution) = results.first() else {
+    return Ok(None);
+  };
+  let level =
+    GeneratedLevel::from_solution(&initial, solution, options.max_assume_depth, options.symmetry, budget)?;
+  let Some(level) = level else {
+    return Ok(None);
+  };
+
+  // the clue search also accepts deductions that merely complete a solution, which does not rule out a second
+  // one, so boards with several solutions need their clues checked
+  if results.len() > 1 {
+    let mut clued = Vec::new();
+    solve_within(level.puzzle(), &mut clued, 2, budget)?;
+    if clued.len() != 1 {
+      return Ok(None);
+    }
+  }
+
+  Ok(Some(GeneratedLevel {
+    seed: Some(seed),
+    ..level
+  }))
+}
+
+// A search node limit for each attempt, so that pathological boards are skipped. Unlike a timeout it keeps batches
+// reproducible; how long the nodes take depends on the board size and empty policy.
+pub const DEFAULT_MAX_NODES: usize = 10_000_000;
+
+// Seeds tried before a batch gives up on finding enough levels, so that targets no board can meet end. Small boards
+// produce a level every few seeds, so this only stops batches that are hopeless or very large.
+pub const DEFAULT_MAX_ATTEMPTS: usize = 10_000;
+
+#[derive(Clone)]
+pub struct BatchOptions {
+  pub count: usize,
+  pub first_seed: u64,
+  pub threads: usize,
+  pub target: Option<DifficultyTarget>,
+  // the batch stops after this many seeds, even with fewer than `count` levels
+  pub max_attempts: Option<usize>,
+  // limits for each attempt, boards that take longer are skipped
+  pub timeout: Option<Duration>,
+  pub max_nodes: Option<usize>,
+  // receives the progress of every attempt, from all threads
+  pub observer: Option<Arc<dyn SearchObserver>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchStats {
+  pub generated: usize,
+  // boards without a solution, or without a clue layout within the assumption depth and symmetry
+  pub failed: usize,
+  // levels that did not pass `verify_level`
+  pub rejected: usize,
+  // levels whose rating lies outside the target
+  pub off_target: usize,
+  // attempts that ran out of time or nodes
+  pub gave_up: usize,
+  pub duplicates: usize,
+  // attempts that panicked, the panic message is printed by the panic hook
+  pub panicked: usize,
+}
+
+enum Attempt {
+  Level(Box<GeneratedLevel>),
+  Failed,
+  Rejected,
+  OffTarget,
+  GaveUp,
+  Panicked,
+}
+
+// One seed of a batch, None once the batch is done
+fn run_attempt(options: &GenerateOptions, batch: &BatchOptions, seed: u64, done: &CancelToken) -> Option<Attempt> {
+  let mut budget = Budget::unlimited().with_cancel(done.clone());
+  if let Some(timeout) = batch.timeout {
+    budget = budget.with_timeout(timeout);
+  }
+  if let Some(max_nodes) = batch.max_nodes {
+    budget = budget.with_max_nodes(max_nodes);
+  }
+  if let Some(observer) = &batch.observer {
+    budget = budget.with_observer(observer.clone());
+  }
+
+  let level = try_generate_within(options, seed, &budget).and_then(|level| match (level, &batch.target) {
+    (Some(level), Some(target)) => retarget(level, target, &budget).map(|level| level.ok_or(Attempt::OffTarget)),
+    (level, _) => Ok(level.ok_or(Attempt::Failed)),
+  });
+  let level = level.and_then(|level| match level {
+    Ok(level) => verify_level_within(&level.level_data(), &budget)
+      .map(|verified| verified.map(|()| level).map_err(|_| Attempt::Rejected)),
+    Err(attempt) => Ok(Err(attempt)),
+  });
+  let attempt = match level {
+    Ok(Ok(level)) => Attempt::Level(Box::new(level)),
+    Ok(Err(attempt)) => attempt,
+    Err(GaveUp::Cancelled) => return None,
+    Err(_) => Attempt::GaveUp,
+  };
+  Some(attempt)
+}
+
+// Tries the seeds `first_seed..`, wrapping around after `u64::MAX`, on `threads` threads until `count` distinct
+// levels, within `target` if given, are found or `max_attempts` seeds are used up. Attempts are taken in seed order
+// no matter which thread finishes first, so the levels passed to `on_level` on the calling thread, along with the
+// stats up to them, and the final stats are the same for any number of threads, unless a timeout cuts attempts
+// short.
+pub fn generate_batch(
+  options: &GenerateOptions,
+  batch: &BatchOptions,
+  mut on_level: impl FnMut(&GeneratedLevel, &BatchStats),
+) -> BatchStats {
+  // attempts are counted separately from seeds, which wrap around after `u64::MAX`
+  let next_attempt = AtomicU64::new(0);
+  let done = CancelToken::new();
+  let mut stats = BatchStats::default();
+  let mut seen = HashSet::new();
+
+  thread::scope(|scope| {
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..batch.threads.max(1) {
+      let sender = sender.clone();
+      let (next_attempt, done) = (&next_attempt, &done);
+      scope.spawn(move || {
+        while !done.is_cancelled() {
+          let index = next_attempt.fetch_add(1, Ordering::Relaxed);
+          if batch.max_attempts.is_some_and(|max| index >= max as u64) {
+            break;
+          }
+          // a panicking attempt still has to be reported, later attempts wait for it
+          let seed = batch.first_seed.wrapping_add(index);
+          let attempt = panic::catch_unwind(AssertUnwindSafe(|| run_attempt(options, batch, seed, done)));
+          let attempt = match attempt {
+            Ok(Some(attempt)) => attempt,
+            Ok(None) => break,
+            Err(_) => Attempt::Panicked,
+          };
+          if sender.send((index, attempt)).is_err() {
+            break;
+          }
+        }
+      });
+    }
+    drop(sender);
+
+    // later attempts wait here until all earlier ones are done
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    let attempts_left = |next: u64| batch.max_attempts.is_none_or(|max| next < max as u64);
+    while stats.generated < batch.count && attempts_left(next) {
+      let Some(attempt) = pending.remove(&next) else {
+        let Ok((index, attempt)) = receiver.recv() else {
+          break;
+        };
+        pending.insert(index, attempt);
+        continue;
+      };
+      next += 1;
+      match attempt {
+        Attempt::Level(level) if seen.insert(level.board_hash()) => {
+          stats.generated += 1;
+          on_level(&level, &stats);
+        }
+        Attempt::Level(_) => stats.duplicates += 1,
+        Attempt::Failed => stats.failed += 1,
+        Attempt::Rejected => stats.rejected += 1,
+        Attempt::OffTarget => stats.off_target += 1,
+        Attempt::GaveUp => stats.gave_up += 1,
+        Attempt::Panicked => stats.panicked += 1,
+      }
+    }
+    done.cancel();
+  });
+
+  stats
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    combinations, generate_batch, try_generate, BatchOptions, GenerateOptions, GeneratedLevel, Minimality,
+  };
+  use crate::board::BoardVec;
+  use crate::budget::{Budget, GaveUp};
+  use crate::rating::{DifficultyTarget, Tier};
+  use crate::serialize::{LevelData, LevelError};
+  use crate::test_util::example_level;
+  use crate::verify::verify_level;
+  use crate::{EmptyPolicy, Field, State, Symmetry};
+
+  // Square boards with ascending empty regions and assumption depth 1
+  fn options(size: u32, symmetry: Option<Symmetry>) -> GenerateOptions {
+    GenerateOptions {
+      width: size,
+      height: size,
+      empty_policy: EmptyPolicy::new_ascending(size, size),
+      max_assume_depth: 1,
+      symmetry,
+    }
+  }
+
+  fn batch(count: usize, first_seed: u64, threads: usize) -> BatchOptions {
+    BatchOptions {
+      count,
+      first_seed,
+      threads,
+      target: None,
+      max_attempts: None,
+      timeout: None,
+      max_nodes: None,
+      observer: None,
+    }
+  }
+
+  #[test]
+  fn test_seed_reproducible() {
+    let options = options(5, None);
+
+    let level = (0..).find_map(|seed| try_generate(&options, seed)).unwrap();
+    let again = try_generate(&options, level.seed.unwrap()).unwrap();
+
+    assert_eq!(level.initial, again.initial);
+    assert_eq!(level.solution, again.solution);
+    assert_eq!(level.initial_open, again.initial_open);
+    assert_eq!(level.steps, again.steps);
+  }
+
+  #[test]
+  fn test_unique_solution() {
+    let options = options(6, None);
+
+    // the first solution of seed 2 used to be kept although its clues allow a second one
+    assert!(try_generate(&options, 2).is_none());
+  }
+
+  #[test]
+  fn test_minimize() {
+    let data =
+      LevelData::from_json(include_str!("../../assets/levels/level_8x8_1_7026781636786016637.json")).unwrap();
+    let level = GeneratedLevel::from_level_data(&data).unwrap();
+    let budget = Budget::unlimited().with_max_nodes(5);
+    assert!(matches!(level.minimize_within(0, &budget), Err(GaveUp::NodeLimit)));
+    let (minimized, minimality) = level.minimize(0);
+
+    assert_eq!(minimality, Minimality::Irreducible);
+    assert!(minimized.initial_open.len() < level.initial_open.len());
+    verify_level(&minimized.level_data()).unwrap();
+    for i in 0..minimized.initial_open.len() {
+      let mut clues = minimized.initial_open.clone();
+      clues.remove(i);
+      assert!(minimized.with_clues(clues).is_none());
+    }
+  }
+
+  #[test]
+  fn test_minimal_with_snake_clues() {
+    let solution: State = "
+      policy: none
+      |X+++.|
+      |...+.|
+      |.+++.|
+      |.+...|
+      |.+++X|
+    "
+    .parse()
+    .unwrap();
+    let initial = State::new(5, 5, BoardVec::new(0, 0), BoardVec::new(4, 4), EmptyPolicy::None);
+    let clues = [(3, 1), (2, 2), (1, 3), (2, 4)]
+      .map(|(x, y)| BoardVec::new(x, y))
+      .to_vec();
+    let level = GeneratedLevel::from_clues(&initial, &solution, clues, 2).unwrap();
+    let (minimized, minimality) = level.minimize(100_000);
+    assert_eq!(minimality, Minimality::Minimal);
+
+    // no smaller set of clues works, whether on snake or on empty fields
+    let unknowns: Vec<BoardVec> = initial
+      .board
+      .positions()
+      .filter(|&pos| initial.field(pos) == Field::Unknown)
+      .collect();
+    for k in 0..minimized.initial_open.len() {
+      assert!(combinations(&unknowns, k).all(|clues| minimized.with_clues(clues).is_none()));
+    }
+  }
+
+  #[test]
+  fn test_symmetric_clues() {
+    let options = options(6, Some(Symmetry::Rotational));
+    let is_symmetric = |clues: &[BoardVec]| {
+      let image = |pos| Symmetry::Rotational.image(pos, 6, 6);
+      clues.iter().all(|&pos| clues.contains(&image(pos)))
+    };
+
+    let level = (0..)
+      .filter_map(|seed| try_generate(&options, seed))
+      .find(|level| verify_level(&level.level_data()).is_ok())
+      .unwrap();
+    assert!(is_symmetric(&level.initial_open));
+
+    let json = serde_json::to_string(&level.level_data()).unwrap();
+    let reloaded = GeneratedLevel::from_level_data(&LevelData::from_json(&json).unwrap()).unwrap();
+    assert_eq!(reloaded.symmetry, Some(Symmetry::Rotational));
+    let code = level.level_data().to_share_code().unwrap();
+    assert_eq!(
+      LevelData::from_share_code(&code).unwrap().symmetry(),
+      Some(Symmetry::Rotational)
+    );
+    let (minimized, _) = reloaded.minimize(0);
+    assert!(is_symmetric(&minimized.initial_open));
+
+    // the symmetry is taken from the file instead of being guessed from the clues
+    let plain = level.level_data().with_symmetry(None);
+    assert_eq!(GeneratedLevel::from_level_data(&plain).unwrap().symmetry, None);
+    let mirrored = example_level().with_symmetry(Some(Symmetry::Horizontal));
+    assert!(matches!(
+      GeneratedLevel::from_level_data(&mirrored),
+      Err(LevelError::AsymmetricClues(Symmetry::Horizontal))
+    ));
+  }
+
+  #[test]
+  fn test_batch() {
+    let options = options(5, None);
+    let batch = batch(3, 0, 2);
+
+    let mut hashes = Vec::new();
+    let stats = generate_batch(&options, &batch, |level, _| hashes.push(level.board_hash()));
+
+    assert_eq!(stats.generated, 3);
+    hashes.sort_unstable();
+    hashes.dedup();
+    assert_eq!(hashes.len(), 3);
+  }
+
+  #[test]
+  fn test_batch_thread_independent() {
+    let options = options(5, None);
+    let run = |threads| {
+      let mut levels = Vec::new();
+      let stats = generate_batch(&options, &batch(3, 1, threads), |level, _| {
+        levels.push((level.seed, level.board_hash()))
+      });
+      (levels, stats)
+    };
+
+    assert_eq!(run(1), run(4));
+  }
+
+  #[test]
+  fn test_batch_target() {
+    let options = options(5, None);
+    let batch = BatchOptions {
+      target: Some(DifficultyTarget::Tier(Tier::Hard)),
+      ..batch(1, 0, 1)
+    };
+
+    let mut tiers = Vec::new();
+    let stats = generate_batch(&options, &batch, |level, _| tiers.push(level.rating().tier));
+
+    assert_eq!(tiers, [Tier::Hard]);
+    assert_eq!(stats.generated, 1);
+  }
+
+  #[test]
+  fn test_batch_seed_wraps() {
+    let options = options(5, None);
+    let batch = batch(3, u64::MAX - 1, 2);
+
+    let mut seeds = Vec::new();
+    let stats = generate_batch(&options, &batch, |level, _| seeds.push(level.seed.unwrap()));
+    assert_eq!(seeds, [u64::MAX - 1, u64::MAX, 3]);
+    assert_eq!(stats.failed, 3);
+  }
+
+  #[test]
+  fn test_batch_max_attempts() {
+    let options = options(4, None);
+    let batch = BatchOptions {
+      target: Some(DifficultyTarget::Score(1000..=1000)),
+      max_attempts: Some(6),
+      ..batch(1, 0, 2)
+    };
+
+    let stats = generate_batch(&options, &batch, |_, _| panic!("no level can meet the target"));
+    assert_eq!(stats.generated, 0);
+    assert_eq!(stats.panicked, 0);
+    assert_eq!(
+      stats.failed + stats.off_target + stats.rejected + stats.gave_up + stats.duplicates,
+      6
+    );
+  }
+}
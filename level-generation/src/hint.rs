@@ -0,0 +1,254 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{BoardExplorer, BoardVec};
+use crate::budget::{Budget, GaveUp};
+use crate::solver::{find_contradiction, FindContradictionResult};
+use crate::{fill_obvious, Deduction, Field, SolveStep, State, Violation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HintRule {
+  // a neighbouring snake has too few unknown neighbours left, so this field must continue it
+  SnakeNeedsNeighbours,
+  // an empty region would be closed at, or grow to, a size the empty policy forbids
+  EmptyRegionSize { size: usize },
+  // a snake here would give a neighbouring snake a third snake neighbour
+  SnakeWouldBranch,
+  // a snake here would join a snake segment with itself
+  SnakeWouldTouchItself,
+  // assuming the other value leads to a contradiction when searching with the given assumption depth
+  Contradiction { depth: usize },
+  // assuming this value completes the puzzle
+  Completes { depth: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+  pub pos: BoardVec,
+  pub field: Field,
+  pub rule: HintRule,
+  // the fields the explanation refers to, including `pos`
+  pub involved: Vec<BoardVec>,
+}
+
+impl fmt::Display for Hint {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let value = if self.field.is_snake() { "snake" } else { "empty" };
+    write!(f, "{:?} must be {value} because ", self.pos)?;
+    match self.rule {
+      HintRule::SnakeNeedsNeighbours => write!(f, "a neighbouring snake has no other way to continue"),
+      HintRule::EmptyRegionSize { size } => {
+        write!(f, "otherwise an empty region would end up with forbidden size {size}")
+      }
+      HintRule::SnakeWouldBranch => write!(f, "a snake here would branch"),
+      HintRule::SnakeWouldTouchItself => write!(f, "a snake here would touch itself"),
+      HintRule::Contradiction { depth } => {
+        write!(f, "the opposite leads to a contradiction (assumption depth {depth})")
+      }
+      HintRule::Completes { .. } => write!(f, "this completes the puzzle"),
+    }
+  }
+}
+
+// The empty fields connected to `start`, which itself may still be unknown
+fn region(state: &State, start: BoardVec) -> Vec<BoardVec> {
+  let mut explorer = BoardExplorer::from(&state.board);
+  let mut region = Vec::new();
+  explorer.enqueue(start);
+
+  while let Some(pos) = explorer.pop() {
+    region.push(pos);
+    for p in state.pos_around(pos) {
+      if state.field(p).is_empty() {
+        explorer.enqueue(p);
+      }
+    }
+  }
+
+  region
+}
+
+fn explain(state: &State, pos: BoardVec, field: Field, violation: Violation) -> Hint {
+  let (rule, mut involved) = match violation {
+    Violation::OutOfBounds | Violation::UnknownValue | Violation::AlreadySet => {
+      unreachable!("hints are only given for unknown fields")
+    }
+    Violation::SnakeStarved { at } => {
+      let involved = state.pos_around(at).chain([at]).filter(|&p| p != pos).collect();
+      (HintRule::SnakeNeedsNeighbours, involved)
+    }
+    // `at` is `pos` itself when an empty field there would join regions, and the region a snake there would close
+    // otherwise
+    Violation::EmptyRegionSize { at, size } => {
+      let involved = region(state, at).into_iter().filter(|&p| p != pos).collect();
+      (HintRule::EmptyRegionSize { size }, involved)
+    }
+    Violation::SnakeBranch { at } => {
+      let involved = state
+        .pos_around(at)
+        .filter(|&p| state.field(p).is_snake())
+        .chain([at])
+        .filter(|&p| p != pos)
+        .collect();
+      (HintRule::SnakeWouldBranch, involved)
+    }
+    Violation::SnakeTouchesItself { a, b } => (HintRule::SnakeWouldTouchItself, vec![a, b]),
+  };
+
+  involved.insert(0, pos);
+  Hint {
+    pos,
+    field,
+    rule,
+    involved,
+  }
+}
+
+pub fn obvious_hint(state: &State, pos: BoardVec) -> Option<Hint> {
+  if state.field(pos) != Field::Unknown {
+    return None;
+  }
+
+  match (state.snake_violation(pos), state.empty_violation(pos)) {
+    (Some(violation), None) => Some(explain(state, pos, Field::Empty, violation)),
+    (None, Some(violation)) => Some(explain(state, pos, Field::Snake, violation)),
+    _ => None,
+  }
+}
+
+pub fn assumption_hint(state: &State, pos: BoardVec, depth: usize) -> Option<Hint> {
+  assumption_hint2(state, pos, depth, &Budget::unlimited())
+}
+
+fn assumption_hint2(state: &State, pos: BoardVec, depth: usize, budget: &Budget) -> Option<Hint> {
+  for (assumed, other) in [(Field::Snake, Field::Empty), (Field::Empty, Field::Snake)] {
+    let mut s = state.clone();
+    if s.try_set(pos, assumed).is_err() {
+      continue;
+    }
+
+    let (field, rule) = match find_contradiction(s.clone(), depth, pos, budget) {
+      FindContradictionResult::Contradiction => (other, HintRule::Contradiction { depth }),
+      FindContradictionResult::Solved => (assumed, HintRule::Completes { depth }),
+      FindContradictionResult::None => continue,
+    };
+
+    // the fields that follow directly from the assumption show where it goes wrong
+    let mut involved = vec![pos];
+    fill_obvious(s, &mut involved);
+    return Some(Hint {
+      pos,
+      field,
+      rule,
+      involved,
+    });
+  }
+
+  None
+}
+
+// Explains a step of a solve trace, `state` is the board right before the step.
+pub fn explain_step(state: &State, step: &SolveStep) -> Option<Hint> {
+  let hint = match step.deduction {
+    Deduction::Obvious => obvious_hint(state, step.pos),
+    Deduction::Assumption(depth) => assumption_hint(state, step.pos, depth),
+  };
+  hint.filter(|hint| hint.field == step.field)
+}
+
+// Finds the simplest deduction possible in `state`, preferring fields that follow without assumption.
+pub fn next_hint(state: &State, max_assume_depth: usize) -> Option<Hint> {
+  next_hint_within(state, max_assume_depth, &Budget::unlimited()).expect("an unlimited budget never runs out")
+}
+
+pub fn next_hint_within(state: &State, max_assume_depth: usize, budget: &Budget) -> Result<Option<Hint>, GaveUp> {
+  let hint = next_hint2(state, max_assume_depth, budget);
+  budget.check(hint)
+}
+
+fn next_hint2(state: &State, max_assume_depth: usize, budget: &Budget) -> Option<Hint> {
+  let unknowns: Vec<BoardVec> = state
+    .board
+    .positions()
+    .filter(|&pos| state.field(pos) == Field::Unknown)
+    .collect();
+
+  unknowns.iter().find_map(|&pos| obvious_hint(state, pos)).or_else(|| {
+    (1..=max_assume_depth).find_map(|depth| {
+      unknowns
+        .iter()
+        .find_map(|&pos| assumption_hint2(state, pos, depth, budget))
+    })
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{next_hint, obvious_hint, HintRule};
+  use crate::board::BoardVec;
+  use crate::test_util::example_level;
+  use crate::{canonical_trace, EmptyPolicy, Field, State};
+
+  #[test]
+  fn test_snake_needs_neighbours() {
+    let mut state = State::new(3, 3, BoardVec::new(0, 0), BoardVec::new(2, 2), EmptyPolicy::None);
+    state.set(BoardVec::new(1, 0), Field::Empty);
+
+    let hint = next_hint(&state, 0).unwrap();
+    assert_eq!(hint.pos, BoardVec::new(0, 1));
+    assert_eq!(hint.field, Field::Snake);
+    assert_eq!(hint.rule, HintRule::SnakeNeedsNeighbours);
+    assert!(hint.involved.contains(&BoardVec::new(0, 0)));
+  }
+
+  #[test]
+  fn test_contradictory_board() {
+    // the field at (1, 0) can be neither snake nor empty, so it cannot be assumed either
+    let mut state = State::new(3, 3, BoardVec::new(0, 0), BoardVec::new(1, 1), EmptyPolicy::None);
+    for (x, y, field) in [
+      (0, 1, Field::Empty),
+      (0, 2, Field::Empty),
+      (1, 2, Field::Snake),
+      (2, 2, Field::Snake),
+    ] {
+      state.try_set(BoardVec::new(x, y), field).unwrap();
+    }
+    let hint = next_hint(&state, 2);
+    assert!(hint.is_none_or(|hint| hint.pos != BoardVec::new(1, 0)));
+  }
+
+  #[test]
+  fn test_closed_region_involves_only_itself() {
+    // a snake at (1, 0) would close the region at (0, 0) with size 1, the region at (2, 0) has nothing to do with it
+    let state: State = "
+      policy: range 2 5
+      |. .  |
+      |+    |
+      |X   X|
+    "
+    .parse()
+    .unwrap();
+
+    let hint = obvious_hint(&state, BoardVec::new(1, 0)).unwrap();
+    assert_eq!(hint.field, Field::Empty);
+    assert_eq!(hint.rule, HintRule::EmptyRegionSize { size: 1 });
+    assert_eq!(hint.involved, [BoardVec::new(1, 0), BoardVec::new(0, 0)]);
+  }
+
+  #[test]
+  fn test_solution_follows_hints() {
+    let data = example_level();
+    let (solution, mut state) = data.states().unwrap();
+    let (_, steps) = canonical_trace(&state, data.max_assumption_depth()).unwrap();
+
+    // the hints walk along the canonical trace
+    for step in steps {
+      let hint = next_hint(&state, data.max_assumption_depth()).unwrap();
+      assert_eq!(hint.field, solution.field(hint.pos), "{hint}");
+      assert_eq!((hint.pos, hint.field), (step.pos, step.field));
+      state.set(hint.pos, hint.field);
+    }
+    assert_eq!(state.unknowns(), 0);
+  }
+}
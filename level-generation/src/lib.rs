@@ -7,14 +7,31 @@ use std::collections::HashMap;
 use std::hash::Hash;
 
 use board::{Board, BoardUnion, BoardUnionFind, BoardVec};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::board::BoardUnionId;
 
 pub mod ai;
 pub mod board;
+pub mod book;
+pub mod budget;
+pub mod generate;
+pub mod hint;
 pub mod list;
+pub mod pack;
+pub mod progress;
+pub mod puzzlink;
+pub mod rating;
 pub mod serialize;
+pub mod session;
+pub mod share;
 pub mod solver;
+pub mod svg;
+pub mod verify;
+
+#[cfg(test)]
+mod test_util;
 
 pub use solver::*;
 
@@ -24,7 +41,7 @@ impl<T> Extend<T> for Throwaway {
   fn extend<I: IntoIterator<Item = T>>(&mut self, _: I) {}
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Field {
   Unknown,
   Snake,
@@ -79,11 +96,54 @@ pub enum SnakeConnectedness {
   Distributed,
 }
 
+// The rule a field would break if it was set to a certain value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Violation {
+  OutOfBounds,
+  // only Snake, SnakeEnd and Empty can be set
+  UnknownValue,
+  AlreadySet,
+  // the snake at `at` would get more than two snake neighbours
+  SnakeBranch { at: BoardVec },
+  // the snake parts `a` and `b` belong to the same segment and would be joined into a loop
+  SnakeTouchesItself { a: BoardVec, b: BoardVec },
+  // the empty region containing `at` would be closed at, or grow to, a size the policy forbids
+  EmptyRegionSize { at: BoardVec, size: usize },
+  // the snake at `at` would not have enough unknown neighbours left to continue
+  SnakeStarved { at: BoardVec },
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Violation::OutOfBounds => write!(f, "field is outside the board"),
+      Violation::UnknownValue => write!(f, "a field cannot be set to unknown"),
+      Violation::AlreadySet => write!(f, "field is already set"),
+      Violation::SnakeBranch { at } => write!(f, "snake would branch at {at:?}"),
+      Violation::SnakeTouchesItself { a, b } => write!(f, "snake would touch itself at {a:?} and {b:?}"),
+      Violation::EmptyRegionSize { at, size } => {
+        write!(f, "empty region at {at:?} would have the forbidden size {size}")
+      }
+      Violation::SnakeStarved { at } => write!(f, "snake at {at:?} would have no room to continue"),
+    }
+  }
+}
+
+impl std::error::Error for Violation {}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub enum EmptyPolicy {
   None,
   Fix(usize),
   Ascending(Vec<bool>, usize),
+  // every enclosed region has between min and max fields (inclusive)
+  Range(usize, usize),
+  // the enclosed regions have exactly the given sizes (sorted), the flags mark the sizes already used
+  Sizes(Vec<usize>, Vec<bool>),
+  // every enclosed region has a different size, without an upper limit
+  Distinct(Vec<bool>),
+  Odd,
+  Even,
 }
 
 impl EmptyPolicy {
@@ -101,6 +161,20 @@ impl EmptyPolicy {
     Self::Ascending(Vec::new(), (max - 1) as usize)
   }
 
+  pub fn new_sizes(mut sizes: Vec<usize>) -> Self {
+    sizes.sort_unstable();
+    let used = vec![false; sizes.len()];
+    Self::Sizes(sizes, used)
+  }
+
+  pub fn new_distinct() -> Self {
+    Self::Distinct(Vec::new())
+  }
+
+  fn unused_sizes<'a>(sizes: &'a [usize], used: &'a [bool]) -> impl Iterator<Item = usize> + 'a {
+    sizes.iter().zip(used).filter(|(_, &used)| !used).map(|(&size, _)| size)
+  }
+
   pub fn allowed(&self, empty_fields: usize) -> bool {
     match self {
       EmptyPolicy::None => true,
@@ -108,6 +182,11 @@ impl EmptyPolicy {
       EmptyPolicy::Ascending(v, _) => {
         self.could_become_allowed(empty_fields) && !v.get(empty_fields - 1).unwrap_or(&false)
       }
+      &EmptyPolicy::Range(min, max) => (min..=max).contains(&empty_fields),
+      EmptyPolicy::Sizes(sizes, used) => Self::unused_sizes(sizes, used).any(|size| size == empty_fields),
+      EmptyPolicy::Distinct(v) => !v.get(empty_fields - 1).unwrap_or(&false),
+      EmptyPolicy::Odd => !empty_fields.is_multiple_of(2),
+      EmptyPolicy::Even => empty_fields.is_multiple_of(2),
     }
   }
 
@@ -116,6 +195,9 @@ impl EmptyPolicy {
       EmptyPolicy::None => true,
       &EmptyPolicy::Fix(n) => empty_fields <= n,
       EmptyPolicy::Ascending(_, max) => empty_fields <= *max,
+      &EmptyPolicy::Range(_, max) => empty_fields <= max,
+      EmptyPolicy::Sizes(sizes, used) => Self::unused_sizes(sizes, used).any(|size| size >= empty_fields),
+      EmptyPolicy::Distinct(_) | EmptyPolicy::Odd | EmptyPolicy::Even => true,
     }
   }
 
@@ -131,6 +213,8 @@ impl EmptyPolicy {
           .sum();
         needed <= unenclosed_fields_left
       }
+      EmptyPolicy::Sizes(sizes, used) => Self::unused_sizes(sizes, used).sum::<usize>() <= unenclosed_fields_left,
+      EmptyPolicy::Range(..) | EmptyPolicy::Distinct(_) | EmptyPolicy::Odd | EmptyPolicy::Even => true,
     }
   }
 
@@ -138,13 +222,20 @@ impl EmptyPolicy {
     match self {
       EmptyPolicy::None => (),
       EmptyPolicy::Fix(_) => (),
-      EmptyPolicy::Ascending(v, _) => {
+      EmptyPolicy::Ascending(v, _) | EmptyPolicy::Distinct(v) => {
         if v.len() < empty_fields {
           v.resize(empty_fields, false);
         }
         assert!(!v[empty_fields - 1]);
         v[empty_fields - 1] = true;
       }
+      EmptyPolicy::Sizes(sizes, used) => {
+        let i = (0..sizes.len())
+          .find(|&i| sizes[i] == empty_fields && !used[i])
+          .expect("region size is not allowed");
+        used[i] = true;
+      }
+      EmptyPolicy::Range(..) | EmptyPolicy::Odd | EmptyPolicy::Even => (),
     }
   }
 }
@@ -179,14 +270,12 @@ impl State {
     }
   }
 
-  pub fn new_rand(width: u32, height: u32, ep: EmptyPolicy) -> Self {
-    let mut rng = rand::thread_rng();
-
+  pub fn new_rand(width: u32, height: u32, ep: EmptyPolicy, rng: &mut impl Rng) -> Self {
     let size = BoardVec::new(width as i32, height as i32);
-    let a = size.rand(&mut rng);
+    let a = size.rand(rng);
 
     loop {
-      let b = size.rand(&mut rng);
+      let b = size.rand(rng);
 
       if a.dist(b) >= 2 {
         return Self::new(width, height, a, b, ep);
@@ -222,16 +311,30 @@ impl State {
   }
 
   pub fn set(&mut self, pos: BoardVec, value: Field) {
-    let field = self.field(pos);
+    if let Err(violation) = self.try_set(pos, value) {
+      panic!("Cannot set {value:?} at {pos:?}: {violation}");
+    }
+  }
 
-    assert_eq!(field, Field::Unknown);
+  // Like `set`, but leaves the state untouched and names the broken rule if the move is not allowed.
+  pub fn try_set(&mut self, pos: BoardVec, value: Field) -> Result<(), Violation> {
+    if self.board.get(pos).is_none() {
+      return Err(Violation::OutOfBounds);
+    }
+    let violation = match value {
+      Field::Unknown => Some(Violation::UnknownValue),
+      Field::Snake | Field::SnakeEnd => self.snake_violation(pos),
+      Field::Empty => self.empty_violation(pos),
+    };
+    if let Some(violation) = violation {
+      return Err(violation);
+    }
 
     self.unknowns -= 1;
 
     match value {
-      Field::Unknown => panic!("Cannot set Field::Unknown"),
+      Field::Unknown => unreachable!(),
       Field::Snake | Field::SnakeEnd => {
-        assert!(self.snake_allowed(pos));
         self.board[pos] = value;
         self.snake_count += 1;
 
@@ -255,7 +358,6 @@ impl State {
         }
       }
       Field::Empty => {
-        assert!(self.empty_allowed(pos));
         self.board[pos] = value;
         self.unenclosed_empties += 1;
 
@@ -276,6 +378,8 @@ impl State {
         }
       }
     }
+
+    Ok(())
   }
 
   pub fn is_dangling_snake(&self, pos: BoardVec) -> bool {
@@ -292,8 +396,12 @@ impl State {
   }
 
   pub fn snake_allowed(&self, pos: BoardVec) -> bool {
+    self.snake_violation(pos).is_none()
+  }
+
+  pub fn snake_violation(&self, pos: BoardVec) -> Option<Violation> {
     if self.field(pos) != Field::Unknown {
-      return false;
+      return Some(Violation::AlreadySet);
     }
 
     let snakes_around = self.snakes_around(pos);
@@ -302,10 +410,10 @@ impl State {
     //}
 
     if snakes_around > 2 {
-      return false;
+      return Some(Violation::SnakeBranch { at: pos });
     }
 
-    let mut first_seg: Option<BoardUnionId> = None;
+    let mut first_seg: Option<(BoardUnionId, BoardVec)> = None;
 
     struct Cluster {
       size: usize,
@@ -326,10 +434,14 @@ impl State {
       let field = self.field(p);
       if field.is_snake() {
         let seg = self.unions[p].id();
-        let segs_are_same = first_seg == Some(seg);
-        first_seg = Some(seg);
-        if segs_are_same || !self.is_dangling_snake(p) {
-          return false;
+        if let Some((first, first_pos)) = first_seg {
+          if first == seg {
+            return Some(Violation::SnakeTouchesItself { a: first_pos, b: p });
+          }
+        }
+        first_seg = Some((seg, p));
+        if !self.is_dangling_snake(p) {
+          return Some(Violation::SnakeBranch { at: p });
         }
       } else if field.is_empty() {
         let u = &self.unions[p];
@@ -339,7 +451,10 @@ impl State {
           if !policy.allowed(cluster.size) {
             //println!("{:?}", pos);
             //println!("{:?}", self);
-            return false;
+            return Some(Violation::EmptyRegionSize {
+              at: p,
+              size: cluster.size,
+            });
           }
           policy.notify(cluster.size);
         }
@@ -347,12 +462,16 @@ impl State {
       }
     }
 
-    true
+    None
   }
 
   pub fn empty_allowed(&self, pos: BoardVec) -> bool {
+    self.empty_violation(pos).is_none()
+  }
+
+  pub fn empty_violation(&self, pos: BoardVec) -> Option<Violation> {
     if self.field(pos) != Field::Unknown {
-      return false;
+      return Some(Violation::AlreadySet);
     }
 
     let mut cluster_count = 1;
@@ -373,18 +492,26 @@ impl State {
         debug_assert!(unknown_around > 0);
 
         if unknown_around <= field.max_snake_neighbours() - snakes_around {
-          return false;
+          return Some(Violation::SnakeStarved { at: p });
         }
       }
     }
 
     let will_not_be_closed = self.unions[pos].data() >= 1 || empty_clusters.values().any(|&c| c > 0);
-    self.empty_policy.allowed(cluster_count)
+    if self.empty_policy.allowed(cluster_count)
       || self.empty_policy.could_become_allowed(cluster_count) && will_not_be_closed
+    {
+      None
+    } else {
+      Some(Violation::EmptyRegionSize {
+        at: pos,
+        size: cluster_count,
+      })
+    }
   }
 
   pub fn is_snake_connected(&self) -> SnakeConnectedness {
-    let a = self.snake_ends.get(0);
+    let a = self.snake_ends.first();
     let b = self.snake_ends.get(1);
 
     if let (Some(&a), Some(&b)) = (a, b) {
@@ -403,6 +530,14 @@ impl State {
   pub fn unknowns(&self) -> u32 {
     self.unknowns
   }
+
+  pub fn snake_ends(&self) -> &[BoardVec] {
+    &self.snake_ends
+  }
+
+  pub fn empty_policy(&self) -> &EmptyPolicy {
+    &self.empty_policy
+  }
 }
 
 impl Eq for State {}
@@ -449,3 +584,50 @@ fn interesting_fields(state: &State) -> impl Iterator<Item = BoardVec> + '_ {
     state.field(pos) == Field::Unknown && state.pos_around(pos).any(|p| state.field(p) != Field::Unknown)
   })
 }*/
+
+#[cfg(test)]
+mod tests {
+  use crate::board::BoardVec;
+  use crate::{EmptyPolicy, Field, State, Violation};
+
+  #[test]
+  fn test_try_set() {
+    let v = BoardVec::new;
+    let mut state = State::new(3, 3, v(0, 0), v(2, 2), EmptyPolicy::None);
+    assert_eq!(state.try_set(v(3, 0), Field::Snake), Err(Violation::OutOfBounds));
+    assert_eq!(state.try_set(v(1, 0), Field::Unknown), Err(Violation::UnknownValue));
+    assert_eq!(state.try_set(v(0, 0), Field::Empty), Err(Violation::AlreadySet));
+
+    state.try_set(v(1, 0), Field::Snake).unwrap();
+    assert_eq!(
+      state.try_set(v(0, 1), Field::Snake),
+      Err(Violation::SnakeBranch { at: v(0, 0) })
+    );
+    assert_eq!(state.unknowns(), 6);
+
+    let mut state = State::new(4, 4, v(0, 0), v(3, 3), EmptyPolicy::None);
+    state.set(v(1, 1), Field::Snake);
+    state.set(v(2, 1), Field::Snake);
+    state.set(v(2, 2), Field::Snake);
+    assert!(matches!(
+      state.try_set(v(1, 2), Field::Snake),
+      Err(Violation::SnakeTouchesItself { .. })
+    ));
+
+    let mut state = State::new(3, 3, v(0, 0), v(2, 2), EmptyPolicy::None);
+    state.set(v(1, 0), Field::Empty);
+    assert_eq!(
+      state.try_set(v(0, 1), Field::Empty),
+      Err(Violation::SnakeStarved { at: v(0, 0) })
+    );
+
+    let mut state = State::new(3, 3, v(0, 0), v(2, 2), EmptyPolicy::Fix(2));
+    state.set(v(1, 0), Field::Snake);
+    state.set(v(1, 1), Field::Snake);
+    state.set(v(0, 1), Field::Empty);
+    assert_eq!(
+      state.try_set(v(0, 2), Field::Snake),
+      Err(Violation::EmptyRegionSize { at: v(0, 1), size: 1 })
+    );
+  }
+}
@@ -1,155 +1,743 @@
-use std::collections::hash_map::DefaultHasher;
-use std::fs;
-use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, process, thread};
 
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use snake::board::BoardVec;
+use snake::book::{render_book, BookOptions};
+use snake::budget::Budget;
+use snake::generate::{
+  generate_batch, BatchOptions, GenerateOptions, GeneratedLevel, Minimality, DEFAULT_MAX_ATTEMPTS,
+  DEFAULT_MAX_NODES,
+};
+use snake::hint::next_hint;
+use snake::pack::{LevelPack, PackError, MANIFEST_FILE};
+use snake::progress::SearchProgress;
+use snake::rating::{DifficultyTarget, Tier};
 use snake::serialize::LevelData;
-use snake::{find_solution_path, solve, Field, State};
+use snake::svg::{render_svg, SvgOptions};
+use snake::verify::{verify_level, VerifyError};
+use snake::{count_solutions_within, solve, EmptyPolicy, State, Symmetry};
 
-#[allow(unused)]
-fn main() {
-  let width = 8;
-  let height = 8;
+#[derive(Parser)]
+#[command(about = "Generate, solve and inspect snake logic puzzles")]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
 
-  let mut a = 0;
-  loop {
-    let game = State::new_rand(width, height, snake::EmptyPolicy::new_ascending(width, height));
-    let mut results = Vec::new();
-    solve(game.clone(), &mut results, 2);
+#[derive(Subcommand)]
+enum Command {
+  /// Generate levels and write them as json files
+  Generate {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// Maximum assumption depth the player needs to solve the level
+    #[arg(long, default_value_t = 1)]
+    max_assume_depth: usize,
+    /// Number of levels to generate
+    #[arg(long, default_value_t = 1)]
+    count: usize,
+    /// Directory the levels are written to
+    #[arg(long, default_value = "./level_out")]
+    out_dir: PathBuf,
+    /// Number of worker threads; all cores if omitted
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Only keep levels of this difficulty tier (easy, medium, hard or expert)
+    #[arg(long, conflicts_with_all = ["min_score", "max_score"])]
+    tier: Option<Tier>,
+    /// Only keep levels with at least this difficulty score
+    #[arg(long)]
+    min_score: Option<u32>,
+    /// Only keep levels with at most this difficulty score
+    #[arg(long)]
+    max_score: Option<u32>,
+    /// Place clues symmetrically (rotational, horizontal, vertical or diagonal)
+    #[arg(long)]
+    symmetry: Option<Symmetry>,
+    /// Skip boards that take longer than this many milliseconds
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+    /// Skip boards that need more than this many search nodes
+    #[arg(long, default_value_t = DEFAULT_MAX_NODES)]
+    max_nodes: usize,
+    /// Stop after trying this many seeds, even with fewer levels than --count
+    #[arg(long, default_value_t = DEFAULT_MAX_ATTEMPTS)]
+    max_attempts: usize,
+    /// Show the progress of the clue search on stderr
+    #[arg(long)]
+    progress: bool,
+  },
+  /// Print the solutions of a board
+  Solve {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// Maximum number of solutions to search for
+    #[arg(long, default_value_t = 2)]
+    max_results: usize,
+  },
+  /// Count the solutions of a board up to a cap
+  Count {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// Stop counting after this many solutions
+    #[arg(long, default_value_t = 100)]
+    cap: usize,
+    /// Give up after this many milliseconds
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+  },
+  /// Check whether a board has exactly one solution, or verify level files
+  Verify {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// Level files to verify
+    files: Vec<PathBuf>,
+  },
+  /// Print the clues and moves needed to solve a board
+  Rate {
+    #[command(flatten)]
+    board: BoardArgs,
+    #[arg(long, default_value_t = 1)]
+    max_assume_depth: usize,
+    /// Store the rating in the level file given with --level
+    #[arg(long, requires = "level")]
+    write: bool,
+  },
+  /// Explain the next deduction possible on a board
+  Hint {
+    #[command(flatten)]
+    board: BoardArgs,
+    #[arg(long, default_value_t = 1)]
+    max_assume_depth: usize,
+  },
+  /// Remove clues from level files as long as they stay solvable
+  Minimize {
+    /// Level files to minimize
+    files: Vec<PathBuf>,
+    /// Maximum number of smaller clue sets to try when checking whether the result is minimal
+    #[arg(long, default_value_t = 10_000)]
+    exhaustive_limit: usize,
+    /// Give up on a level after this many milliseconds
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+    /// Directory the minimized levels are written to
+    #[arg(long, default_value = "./level_out")]
+    out_dir: PathBuf,
+  },
+  /// Lay out level files as printable pages, with the solutions at the back
+  Book {
+    /// Level files in the order they appear in the book
+    files: Vec<PathBuf>,
+    #[arg(long, default_value = "Snake puzzles")]
+    title: String,
+    /// Puzzles per row
+    #[arg(long, default_value_t = 2)]
+    columns: u32,
+    /// Rows of puzzles per page
+    #[arg(long, default_value_t = 3)]
+    rows: u32,
+    /// Directory the pages are written to, one SVG file per page
+    #[arg(long, default_value = "./book")]
+    out_dir: PathBuf,
+  },
+  /// Bundle level files into a pack, or split a pack into level files
+  Pack {
+    #[command(subcommand)]
+    command: PackCommand,
+  },
+  /// Print the share codes of level files
+  Encode {
+    /// Level files to encode
+    files: Vec<PathBuf>,
+    /// Print puzz.link URLs of the Snake genre instead, only for levels without empty policy
+    #[arg(long)]
+    puzz_link: bool,
+  },
+  /// Write the level of a share code or puzz.link URL to a file
+  Decode {
+    code: String,
+    /// Maximum assumption depth for puzz.link URLs, which do not store it
+    #[arg(long, default_value_t = 1)]
+    max_assume_depth: usize,
+    /// Directory the level is written to
+    #[arg(long, default_value = "./level_out")]
+    out_dir: PathBuf,
+  },
+  /// Print a board as puzzle and as solution
+  Render {
+    #[command(flatten)]
+    board: BoardArgs,
+    #[arg(long, default_value_t = 1)]
+    max_assume_depth: usize,
+    /// Write the board as SVG image to this file instead of printing it
+    #[arg(long)]
+    svg: Option<PathBuf>,
+    /// Draw the solution into the SVG image
+    #[arg(long, requires = "svg")]
+    solution: bool,
+    /// Edge length of a field in the SVG image
+    #[arg(long, default_value_t = 40)]
+    cell_size: u32,
+  },
+}
 
-    if !results.is_empty() {
-      for ele in results.iter() {
-        println!("{:?}", ele);
-      }
+#[derive(Subcommand)]
+enum PackCommand {
+  /// Build a pack file from level files, in the given order
+  Build {
+    /// Level files; their file names without extension become the level ids
+    files: Vec<PathBuf>,
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    author: String,
+    /// Order the levels by difficulty tier and score
+    #[arg(long)]
+    sort: bool,
+    /// Pack file to write
+    #[arg(long, default_value = "./pack.json")]
+    out: PathBuf,
+  },
+  /// Write the levels of a pack file or directory as separate files next to a manifest
+  Split {
+    pack: PathBuf,
+    /// Directory the manifest and levels are written to
+    #[arg(long, default_value = "./pack")]
+    out_dir: PathBuf,
+  },
+}
 
-      let solution = results.first().unwrap();
-      show_solution(&game, solution, 1);
+#[derive(Args)]
+struct BoardArgs {
+  /// Board width, from 2 to 64
+  #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(2..=64))]
+  width: u32,
+  /// Board height, from 2 to 64
+  #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(2..=64))]
+  height: u32,
+  #[arg(long, value_enum, default_value_t = PolicyArg::Ascending)]
+  policy: PolicyArg,
+  /// Region size for the `fix` policy
+  #[arg(long, default_value_t = 5)]
+  fix_size: usize,
+  /// Smallest region size for the `range` policy
+  #[arg(long, default_value_t = 1)]
+  min_size: usize,
+  /// Largest region size for the `range` policy
+  #[arg(long, default_value_t = 5)]
+  max_size: usize,
+  /// Region sizes for the `sizes` policy as comma separated list, e.g. `1,2,2,4`
+  #[arg(long, value_delimiter = ',', default_values_t = [1, 2, 3])]
+  sizes: Vec<usize>,
+  /// Seed for random boards; generated levels record the seed they were created with
+  #[arg(long)]
+  seed: Option<u64>,
+  /// Level file to load instead of generating a board
+  #[arg(long, conflicts_with_all = ["start", "end"])]
+  level: Option<PathBuf>,
+  /// First snake end as `x,y`; random if omitted
+  #[arg(long, value_parser = parse_vec, requires = "end")]
+  start: Option<BoardVec>,
+  /// Second snake end as `x,y`; random if omitted
+  #[arg(long, value_parser = parse_vec, requires = "start")]
+  end: Option<BoardVec>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PolicyArg {
+  None,
+  Fix,
+  Ascending,
+  Range,
+  Sizes,
+  Distinct,
+  Odd,
+  Even,
+}
 
-    } else {
-      println!("faild ({a})...");
-      a += 1;
+impl BoardArgs {
+  fn empty_policy(&self) -> EmptyPolicy {
+    match self.policy {
+      PolicyArg::None => EmptyPolicy::None,
+      PolicyArg::Fix => EmptyPolicy::Fix(self.fix_size),
+      PolicyArg::Ascending => EmptyPolicy::new_ascending(self.width, self.height),
+      PolicyArg::Range if self.min_size > self.max_size => {
+        invalid_args("--min-size must not be larger than --max-size")
+      }
+      PolicyArg::Range => EmptyPolicy::Range(self.min_size, self.max_size),
+      PolicyArg::Sizes => EmptyPolicy::new_sizes(self.sizes.clone()),
+      PolicyArg::Distinct => EmptyPolicy::new_distinct(),
+      PolicyArg::Odd => EmptyPolicy::Odd,
+      PolicyArg::Even => EmptyPolicy::Even,
     }
   }
-}
 
-/*#[allow(unused)]
-fn main2() {
-  let game = State::new_rand(10, 10, snake::EmptyPolicy::Fix(5)); //new(11, 11, BoardVec::new(7, 8), BoardVec::new(8, 10));
-  println!("{:?}", game);
+  fn seed(&self) -> u64 {
+    self.seed.unwrap_or_else(|| rand::thread_rng().gen())
+  }
 
-  let mut s = Vec::new();
-  let r = solve(game, usize::MAX / 2, &mut s);
-  println!("{:?}", r);
+  fn load_level(&self) -> Option<GeneratedLevel> {
+    let path = self.level.as_ref()?;
+    match LevelData::load(path).and_then(|data| GeneratedLevel::from_level_data(&data)) {
+      Ok(level) => Some(level),
+      Err(err) => {
+        eprintln!("{}: {err}", path.display());
+        process::exit(1);
+      }
+    }
+  }
 
-  if let Ok(SolveResult::Contradiction) = r {
-    s.sort_by_key(|s| u32::MAX - s.1.unknowns());
+  fn state(&self) -> State {
+    if let Some(level) = self.load_level() {
+      return level.puzzle();
+    }
 
-    for (p, s) in s {
-      println!("{:?}", p);
-      println!("{:?}", s);
+    match (self.start, self.end) {
+      (Some(a), Some(b)) => {
+        let in_bounds =
+          |pos: BoardVec| (0..self.width as i32).contains(&pos.x) && (0..self.height as i32).contains(&pos.y);
+        if !in_bounds(a) || !in_bounds(b) {
+          invalid_args(format!(
+            "--start and --end must lie on the {}x{} board",
+            self.width, self.height
+          ));
+        }
+        if a.dist(b) < 2 {
+          invalid_args("--start and --end must be distinct and not adjacent");
+        }
+        State::new(self.width, self.height, a, b, self.empty_policy())
+      }
+      _ => State::new_rand(
+        self.width,
+        self.height,
+        self.empty_policy(),
+        &mut StdRng::seed_from_u64(self.seed()),
+      ),
     }
   }
-}*/
-
-#[allow(unused)]
-fn main3() {
-  let mut game = State::new(
-    11,
-    11,
-    BoardVec::new(7, 8),
-    BoardVec::new(8, 10),
-    snake::EmptyPolicy::Fix(5),
+}
+
+// Reports arguments that clap cannot check on its own, like a usage error.
+fn invalid_args(message: impl std::fmt::Display) -> ! {
+  Cli::command().error(ErrorKind::ValueValidation, message).exit()
+}
+
+fn parse_vec(s: &str) -> Result<BoardVec, String> {
+  let (x, y) = s.split_once(',').ok_or("expected `x,y`")?;
+  let x = x.trim().parse().map_err(|e| format!("invalid x: {e}"))?;
+  let y = y.trim().parse().map_err(|e| format!("invalid y: {e}"))?;
+  Ok(BoardVec::new(x, y))
+}
+
+fn level_or_solve(board: &BoardArgs, max_assume_depth: usize) -> Option<GeneratedLevel> {
+  if let Some(level) = board.load_level() {
+    return Some(level);
+  }
+
+  let state = board.state();
+  let mut results = Vec::new();
+  solve(state.clone(), &mut results, 1);
+  let solution = results.pop()?;
+  GeneratedLevel::from_solution(&state, &solution, max_assume_depth, None, &Budget::unlimited())
+    .expect("an unlimited budget never runs out")
+}
+
+// Overwrites a single status line; with several threads it shows whichever attempt reported last.
+fn show_progress(progress: &SearchProgress) {
+  eprint!(
+    "\r\x1b[K{} in queue, best {} unknown with {} clues, {} nodes, {:.1}s",
+    progress.queue,
+    progress.best_unknowns,
+    progress.clues,
+    progress.nodes,
+    progress.elapsed.as_secs_f64()
   );
+  let _ = std::io::stderr().flush();
+}
 
-  let board = "
-  x-----------x
-  |···+++··+++|
-  |·+++·+·++·+|
-  |·+···+·+··+|
-  |++·+++·+·++|
-  |+·++··++·+·|
-  |+·+··++·++·|
-  |+·++·+··+··|
-  |+··+++··++·|
-  |+++···+X·++|
-  |··++··+···+|
-  |···++++·X++|
-  x-----------x
-  ";
-
-  let mut it = board.chars().filter(|&c| "+·".contains(c));
-
-  for y in 0..11 {
-    for x in 0..11 {
-      let v = BoardVec::new(x, y);
-
-      if game.field(v) == Field::Unknown {
-        println!("{:?}", game);
-        game.set(
-          v,
-          match it.next().unwrap() {
-            '·' => Field::Empty,
-            '+' => Field::Snake,
-            _ => unreachable!(),
-          },
-        );
+fn verify_files(files: &[PathBuf]) {
+  let mut failed = 0;
+  for path in files {
+    match LevelData::load(path)
+      .map_err(VerifyError::from)
+      .and_then(|level| verify_level(&level))
+    {
+      Ok(()) => println!("{}: ok", path.display()),
+      Err(err) => {
+        println!("{}: {err}", path.display());
+        failed += 1;
       }
     }
   }
+
+  if failed > 0 {
+    eprintln!("{failed} of {} level(s) failed verification", files.len());
+    process::exit(1);
+  }
 }
 
-#[allow(unused)]
-fn main5() {
-  let game = State::new(
-    10,
-    10,
-    BoardVec::new(2, 0),
-    BoardVec::new(0, 2),
-    snake::EmptyPolicy::new_ascending(10, 10),
-  ); //new(11, 11, BoardVec::new(7, 8), BoardVec::new(8, 10));
-  println!("{:?}", game);
+fn main() {
+  let cli = Cli::parse();
 
-  let mut results = Vec::new();
-  //let mut s = Vec::new();
-  solve(game.clone(), &mut results, 10);
-  let solution = results.last().unwrap();
-  println!("{:?}", solution);
+  match cli.command {
+    Command::Generate {
+      board,
+      max_assume_depth,
+      count,
+      out_dir,
+      threads,
+      tier,
+      min_score,
+      max_score,
+      symmetry,
+      timeout_ms,
+      max_nodes,
+      max_attempts,
+      progress,
+    } => {
+      if min_score.zip(max_score).is_some_and(|(min, max)| min > max) {
+        invalid_args("--min-score must not be larger than --max-score");
+      }
+      if let Some(symmetry) = symmetry.filter(|symmetry| !symmetry.fits(board.width, board.height)) {
+        invalid_args(format!("{symmetry} symmetry needs a square board"));
+      }
+      let options = GenerateOptions {
+        width: board.width,
+        height: board.height,
+        empty_policy: board.empty_policy(),
+        max_assume_depth,
+        symmetry,
+      };
+
+      let batch = BatchOptions {
+        count,
+        first_seed: board.seed(),
+        threads: threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get())),
+        target: match (tier, min_score, max_score) {
+          (Some(tier), ..) => Some(DifficultyTarget::Tier(tier)),
+          (None, None, None) => None,
+          (None, min, max) => Some(DifficultyTarget::Score(min.unwrap_or(0)..=max.unwrap_or(u32::MAX))),
+        },
+        max_attempts: Some(max_attempts),
+        timeout: timeout_ms.map(Duration::from_millis),
+        max_nodes: Some(max_nodes),
+        observer: progress.then(|| Arc::new(show_progress) as _),
+      };
+
+      let stats = generate_batch(&options, &batch, |level, stats| {
+        if progress {
+          eprint!("\r\x1b[K");
+        }
+        println!("{:?}", level.puzzle());
+        let path = level.write_to(&out_dir).expect("could not write level");
+        let rating = level.rating();
+        println!(
+          "Wrote {} (seed {}, score {} {}; {} off target and {} rejected so far)",
+          path.display(),
+          level.seed.unwrap(),
+          rating.score,
+          rating.tier,
+          stats.off_target,
+          stats.rejected
+        );
+      });
+      if progress {
+        eprint!("\r\x1b[K");
+      }
+      println!(
+        "Generated {} level(s); {} board(s) without solution or clue layout, {} rejected, {} off target, {} gave up, \
+         {} duplicate(s)",
+        stats.generated, stats.failed, stats.rejected, stats.off_target, stats.gave_up, stats.duplicates
+      );
+      if stats.panicked > 0 {
+        println!("{} attempt(s) panicked", stats.panicked);
+      }
+      if stats.generated < count {
+        println!("Stopped after {max_attempts} attempts");
+      }
+    }
+    Command::Solve { board, max_results } => {
+      let state = board.state();
+      println!("{:?}", state);
 
-  //show_solution(&game, solution);
-  /*if let Ok(SolveResult::Contradiction) = r {
-    s.sort_by_key(|s| u32::MAX - s.1.unknowns());
+      let mut results = Vec::new();
+      solve(state, &mut results, max_results);
+      for solution in results.iter() {
+        println!("{:?}", solution);
+      }
+      println!("Found {} solution(s)", results.len());
+    }
+    Command::Count { board, cap, timeout_ms } => {
+      let state = board.state();
+      println!("{:?}", state);
 
-    for (p, s) in s {
-      println!("{:?}", p);
-      println!("{:?}", s);
+      let mut budget = Budget::unlimited();
+      if let Some(timeout_ms) = timeout_ms {
+        budget = budget.with_timeout(Duration::from_millis(timeout_ms));
+      }
+      let count = match count_solutions_within(state, cap, &budget) {
+        Ok(count) => count,
+        Err(gave_up) => {
+          println!("{gave_up} after {} search nodes", budget.nodes());
+          process::exit(1);
+        }
+      };
+      if count.exhausted {
+        println!("Solutions: {}", count.solutions);
+      } else {
+        println!("Solutions: at least {} (cap reached)", count.solutions);
+      }
+      println!("Search nodes: {}", count.nodes);
+      println!("Max recursion depth: {}", count.max_depth);
     }
-  }*/
-}
+    Command::Verify { board, mut files } => {
+      files.extend(board.level.clone());
+      if !files.is_empty() {
+        verify_files(&files);
+        return;
+      }
 
-fn show_solution(initial: &State, solution: &State, max_assume_depth: usize) {
-  let (initial_open, moves) = find_solution_path(initial.clone(), solution, max_assume_depth);
+      let state = board.state();
+      println!("{:?}", state);
 
-  let mut state = initial.clone();
+      let mut results = Vec::new();
+      solve(state, &mut results, 2);
+      match results.len() {
+        0 => println!("No solution"),
+        1 => println!("Unique solution"),
+        _ => println!("Multiple solutions"),
+      }
+    }
+    Command::Rate {
+      board,
+      max_assume_depth,
+      write,
+    } => {
+      let Some(level) = level_or_solve(&board, max_assume_depth) else {
+        println!("No solution");
+        return;
+      };
 
-  for &pos in initial_open.iter() {
-    state.set(pos, solution.field(pos));
-  }
+      let rating = level.rating();
+      println!("{:?}", level.puzzle());
+      println!("Max assumption depth: {}", level.max_assume_depth);
+      println!("Clues: {}", rating.clues);
+      println!("Moves: {}", level.steps.len());
+      println!("Obvious deductions: {}", rating.obvious);
+      for (depth, count) in rating.assumptions.iter().enumerate() {
+        println!("Deductions with assumption depth {}: {count}", depth + 1);
+      }
+      println!("Score: {} ({})", rating.score, rating.tier);
 
-  println!("{:?}", state);
+      if let Some(path) = board.level.as_ref().filter(|_| write) {
+        let written = LevelData::load(path).and_then(|data| {
+          let data = data.with_difficulty(Some(rating.difficulty()));
+          fs::write(path, serde_json::to_string_pretty(&data)?)?;
+          Ok(())
+        });
+        if let Err(err) = written {
+          eprintln!("{}: {err}", path.display());
+          process::exit(1);
+        }
+        println!("Wrote {}", path.display());
+      }
+    }
+    Command::Hint {
+      board,
+      max_assume_depth,
+    } => {
+      let state = board.state();
+      println!("{:?}", state);
+      match next_hint(&state, max_assume_depth) {
+        Some(hint) => {
+          println!("{hint}");
+          println!("Involved fields: {:?}", hint.involved);
+        }
+        None => println!("No deduction possible"),
+      }
+    }
+    Command::Minimize {
+      files,
+      exhaustive_limit,
+      timeout_ms,
+      out_dir,
+    } => {
+      for path in files {
+        let level = match LevelData::load(&path).and_then(|data| GeneratedLevel::from_level_data(&data)) {
+          Ok(level) => level,
+          Err(err) => {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+          }
+        };
 
-  let level = LevelData::new(solution, initial_open, moves, max_assume_depth);
-  let serialized = serde_json::to_string_pretty(&level).unwrap();
-  println!("{serialized}");
+        let mut budget = Budget::unlimited();
+        if let Some(timeout_ms) = timeout_ms {
+          budget = budget.with_timeout(Duration::from_millis(timeout_ms));
+        }
+        let (minimized, minimality) = match level.minimize_within(exhaustive_limit, &budget) {
+          Ok(minimized) => minimized,
+          Err(gave_up) => {
+            eprintln!("{}: {gave_up}", path.display());
+            process::exit(1);
+          }
+        };
+        let written = minimized.write_to(&out_dir).expect("could not write level");
+        let minimality = match minimality {
+          Minimality::Minimal => "minimal",
+          Minimality::Irreducible => "irreducible",
+        };
+        println!(
+          "{}: {} -> {} clue(s), {minimality}, wrote {}",
+          path.display(),
+          level.initial_open.len(),
+          minimized.initial_open.len(),
+          written.display()
+        );
+      }
+    }
+    Command::Book {
+      files,
+      title,
+      columns,
+      rows,
+      out_dir,
+    } => {
+      let levels: Vec<LevelData> = files
+        .iter()
+        .map(|path| {
+          LevelData::load(path).unwrap_or_else(|err| {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+          })
+        })
+        .collect();
+      let options = BookOptions { title, columns, rows };
+      let pages = render_book(&levels, &options).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+      });
 
-  let hasher = &mut DefaultHasher::new();
-  initial.hash(hasher);
-  let filename = format!(
-    "./level_out/level_{}x{}_{}_{}.json",
-    initial.width(),
-    initial.height(),
-    max_assume_depth,
-    hasher.finish()
-  );
-  let _ = fs::create_dir("./level_out");
-  fs::write(filename, serialized).unwrap();
+      fs::create_dir_all(&out_dir).expect("could not create output directory");
+      for (i, page) in pages.iter().enumerate() {
+        let path = out_dir.join(format!("page_{:03}.svg", i + 1));
+        fs::write(&path, page).expect("could not write page");
+      }
+      println!("Wrote {} page(s) to {}", pages.len(), out_dir.display());
+    }
+    Command::Pack { command } => match command {
+      PackCommand::Build {
+        files,
+        name,
+        author,
+        sort,
+        out,
+      } => {
+        let mut pack = LevelPack::new(name, author);
+        for path in files {
+          let id = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+          let pushed = LevelData::load(&path)
+            .map_err(PackError::from)
+            .and_then(|level| pack.push(id, level));
+          if let Err(err) = pushed {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+          }
+        }
+        if sort {
+          pack.sort_by_tier();
+        }
+
+        fs::write(&out, pack.to_json()).expect("could not write pack");
+        println!("Wrote {} level(s) to {}", pack.levels().len(), out.display());
+      }
+      PackCommand::Split { pack, out_dir } => {
+        let files = LevelPack::load(&pack).and_then(|pack| pack.split(&out_dir));
+        match files {
+          Ok(files) => println!(
+            "Wrote {} level(s) and {MANIFEST_FILE} to {}",
+            files.len(),
+            out_dir.display()
+          ),
+          Err(err) => {
+            eprintln!("{}: {err}", pack.display());
+            process::exit(1);
+          }
+        }
+      }
+    },
+    Command::Encode { files, puzz_link } => {
+      for path in files {
+        let code = LevelData::load(&path).map_err(|err| err.to_string()).and_then(|data| {
+          if puzz_link {
+            data.to_puzz_link().map_err(|err| err.to_string())
+          } else {
+            data.to_share_code().map_err(|err| err.to_string())
+          }
+        });
+        match code {
+          Ok(code) => println!("{}: {code}", path.display()),
+          Err(err) => {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+          }
+        }
+      }
+    }
+    Command::Decode {
+      code,
+      max_assume_depth,
+      out_dir,
+    } => {
+      // share codes never contain a slash
+      let data = if code.contains('/') {
+        LevelData::from_puzz_link(&code, max_assume_depth).map_err(|err| err.to_string())
+      } else {
+        LevelData::from_share_code(&code).map_err(|err| err.to_string())
+      };
+      let level = data.and_then(|data| GeneratedLevel::from_level_data(&data).map_err(|err| err.to_string()));
+      match level {
+        Ok(level) => {
+          println!("{:?}", level.puzzle());
+          let path = level.write_to(&out_dir).expect("could not write level");
+          println!("Wrote {}", path.display());
+        }
+        Err(err) => {
+          eprintln!("{err}");
+          process::exit(1);
+        }
+      }
+    }
+    Command::Render {
+      board,
+      max_assume_depth,
+      svg,
+      solution,
+      cell_size,
+    } => {
+      let Some(level) = level_or_solve(&board, max_assume_depth) else {
+        println!("No solution");
+        return;
+      };
+
+      if let Some(path) = svg {
+        let options = SvgOptions { cell_size, solution };
+        fs::write(&path, render_svg(&level.puzzle(), &level.solution, &options)).expect("could not write image");
+        println!("Wrote {}", path.display());
+        return;
+      }
+
+      println!("Puzzle:\n{:?}", level.puzzle());
+      println!("Solution:\n{:?}", level.solution);
+    }
+  }
 }
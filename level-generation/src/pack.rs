@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::generate::GeneratedLevel;
+use crate::rating::Tier;
+use crate::serialize::{LevelData, LevelError};
+
+pub const PACK_FORMAT_VERSION: u32 = 1;
+// name of the manifest in pack directories
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug)]
+pub enum PackError {
+  Level(LevelError),
+  UnsupportedVersion(u32),
+  InvalidLevelId(String),
+  DuplicateLevelId(String),
+  // a manifest entry with neither inline level nor file
+  MissingLevel(String),
+  // a level file outside the pack directory
+  InvalidLevelFile(String),
+}
+
+impl fmt::Display for PackError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PackError::Level(err) => write!(f, "{err}"),
+      PackError::UnsupportedVersion(version) => write!(
+        f,
+        "unsupported pack format version {version}, at most {PACK_FORMAT_VERSION} is supported"
+      ),
+      PackError::InvalidLevelId(id) => write!(f, "'{id}' cannot be used as level id"),
+      PackError::DuplicateLevelId(id) => write!(f, "level id '{id}' is used more than once"),
+      PackError::MissingLevel(id) => write!(f, "pack entry '{id}' has no level"),
+      PackError::InvalidLevelFile(file) => write!(f, "level file '{file}' lies outside the pack directory"),
+    }
+  }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<LevelError> for PackError {
+  fn from(err: LevelError) -> Self {
+    PackError::Level(err)
+  }
+}
+
+impl From<io::Error> for PackError {
+  fn from(err: io::Error) -> Self {
+    PackError::Level(err.into())
+  }
+}
+
+impl From<serde_json::Error> for PackError {
+  fn from(err: serde_json::Error) -> Self {
+    PackError::Level(err.into())
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackLevel {
+  pub id: String,
+  pub tier: Tier,
+  pub level: LevelData,
+}
+
+// An ordered collection of levels. It is stored either as a single file with the levels inline, or as a directory
+// with a manifest next to one file per level.
+#[derive(Debug, Clone)]
+pub struct LevelPack {
+  pub name: String,
+  pub author: String,
+  levels: Vec<PackLevel>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+  version: u32,
+  name: String,
+  author: String,
+  levels: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+  id: String,
+  tier: Tier,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  file: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  level: Option<LevelData>,
+}
+
+impl LevelPack {
+  pub fn new(name: String, author: String) -> Self {
+    Self {
+      name,
+      author,
+      levels: Vec::new(),
+    }
+  }
+
+  pub fn levels(&self) -> &[PackLevel] {
+    &self.levels
+  }
+
+  // Appends a level, its tier is taken from the stored difficulty or rated if the level has none.
+  pub fn push(&mut self, id: String, level: LevelData) -> Result<(), PackError> {
+    let tier = match level.difficulty() {
+      Some(difficulty) => difficulty.tier,
+      None => GeneratedLevel::from_level_data(&level)?.rating().tier,
+    };
+    self.push_with_tier(id, tier, level)
+  }
+
+  // Ids name the files of a split pack, so they must be unique, usable as file names and must not clash with the
+  // manifest, also on case-insensitive file systems.
+  pub fn push_with_tier(&mut self, id: String, tier: Tier, level: LevelData) -> Result<(), PackError> {
+    if id.is_empty()
+      || id.contains(['/', '\\'])
+      || id.starts_with('.')
+      || format!("{id}.json").eq_ignore_ascii_case(MANIFEST_FILE)
+    {
+      return Err(PackError::InvalidLevelId(id));
+    }
+    if self.levels.iter().any(|l| l.id.eq_ignore_ascii_case(&id)) {
+      return Err(PackError::DuplicateLevelId(id));
+    }
+    self.levels.push(PackLevel { id, tier, level });
+    Ok(())
+  }
+
+  // Orders the levels from easy to expert and by score within a tier, levels without score keep their order.
+  pub fn sort_by_tier(&mut self) {
+    self
+      .levels
+      .sort_by_key(|l| (l.tier, l.level.difficulty().map(|d| d.score)));
+  }
+
+  pub fn from_json(json: &str) -> Result<Self, PackError> {
+    Self::from_manifest(serde_json::from_str(json)?, None)
+  }
+
+  // Reads a pack file, or a pack directory containing a manifest
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, PackError> {
+    let path = path.as_ref();
+    if path.is_dir() {
+      let manifest = serde_json::from_str(&fs::read_to_string(path.join(MANIFEST_FILE))?)?;
+      Self::from_manifest(manifest, Some(path))
+    } else {
+      Self::from_json(&fs::read_to_string(path)?)
+    }
+  }
+
+  fn from_manifest(manifest: Manifest, dir: Option<&Path>) -> Result<Self, PackError> {
+    if manifest.version > PACK_FORMAT_VERSION {
+      return Err(PackError::UnsupportedVersion(manifest.version));
+    }
+
+    let mut pack = Self::new(manifest.name, manifest.author);
+    let mut ids = HashSet::new();
+    for entry in manifest.levels {
+      if !ids.insert(entry.id.to_ascii_lowercase()) {
+        return Err(PackError::DuplicateLevelId(entry.id));
+      }
+      let level = match (entry.level, entry.file, dir) {
+        (Some(level), ..) => level,
+        (None, Some(file), Some(dir)) => {
+          // manifests may come from anywhere, so they must not reach files outside their directory
+          let inside = Path::new(&file)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+          if !inside {
+            return Err(PackError::InvalidLevelFile(file));
+          }
+          LevelData::load(dir.join(file))?
+        }
+        _ => return Err(PackError::MissingLevel(entry.id)),
+      };
+      pack.push_with_tier(entry.id, entry.tier, level)?;
+    }
+    Ok(pack)
+  }
+
+  fn manifest(&self, inline: bool) -> Manifest {
+    Manifest {
+      version: PACK_FORMAT_VERSION,
+      name: self.name.clone(),
+      author: self.author.clone(),
+      levels: self
+        .levels
+        .iter()
+        .map(|l| ManifestEntry {
+          id: l.id.clone(),
+          tier: l.tier,
+          file: (!inline).then(|| format!("{}.json", l.id)),
+          level: inline.then(|| l.level.clone()),
+        })
+        .collect(),
+    }
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(&self.manifest(true)).expect("packs always serialize")
+  }
+
+  // Writes the manifest and one file per level into `dir`, returns the level files in pack order.
+  pub fn split(&self, dir: &Path) -> Result<Vec<PathBuf>, PackError> {
+    fs::create_dir_all(dir)?;
+    let manifest = serde_json::to_string_pretty(&self.manifest(false))?;
+    fs::write(dir.join(MANIFEST_FILE), manifest)?;
+
+    let mut paths = Vec::new();
+    for l in self.levels.iter() {
+      let path = dir.join(format!("{}.json", l.id));
+      fs::write(&path, serde_json::to_string_pretty(&l.level)?)?;
+      paths.push(path);
+    }
+    Ok(paths)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs};
+
+  use super::{LevelPack, PackError, MANIFEST_FILE};
+  use crate::rating::Tier;
+  use crate::test_util::example_level;
+
+  #[test]
+  fn test_pack_round_trip() {
+    let mut pack = LevelPack::new("Test".to_string(), "Tester".to_string());
+    pack
+      .push_with_tier("b".to_string(), Tier::Medium, example_level())
+      .unwrap();
+    pack
+      .push_with_tier("a".to_string(), Tier::Easy, example_level())
+      .unwrap();
+    assert!(matches!(
+      pack.push("a".to_string(), example_level()),
+      Err(PackError::DuplicateLevelId(_))
+    ));
+    assert!(matches!(
+      pack.push("../a".to_string(), example_level()),
+      Err(PackError::InvalidLevelId(_))
+    ));
+
+    let reloaded = LevelPack::from_json(&pack.to_json()).unwrap();
+    let ids: Vec<&str> = reloaded.levels().iter().map(|l| l.id.as_str()).collect();
+    assert_eq!(ids, ["b", "a"]);
+    assert_eq!(reloaded.name, "Test");
+
+    let dir = env::temp_dir().join(format!("snake_pack_{}", std::process::id()));
+    let files = pack.split(&dir).unwrap();
+    assert_eq!(files.len(), 2);
+    let from_dir = LevelPack::load(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(from_dir.levels()[1].tier, Tier::Easy);
+    assert_eq!(
+      from_dir.levels()[0].level.solution().unwrap(),
+      pack.levels()[0].level.solution().unwrap()
+    );
+
+    pack.sort_by_tier();
+    assert_eq!(pack.levels()[0].id, "a");
+  }
+
+  #[test]
+  fn test_split_pack_paths() {
+    let mut pack = LevelPack::new("Test".to_string(), "Tester".to_string());
+    for id in ["manifest", "MANIFEST"] {
+      assert!(matches!(
+        pack.push(id.to_string(), example_level()),
+        Err(PackError::InvalidLevelId(_))
+      ));
+    }
+    pack.push("manifest_1".to_string(), example_level()).unwrap();
+    // would be written to the same file on case-insensitive file systems
+    assert!(matches!(
+      pack.push("Manifest_1".to_string(), example_level()),
+      Err(PackError::DuplicateLevelId(_))
+    ));
+
+    let dir = env::temp_dir().join(format!("snake_pack_paths_{}", std::process::id()));
+    pack.split(&dir).unwrap();
+    let reloaded = LevelPack::load(&dir).map(|pack| pack.levels()[0].id.clone());
+
+    let manifest = fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap();
+    for file in ["../manifest_1.json", "/etc/passwd"] {
+      let escaping = manifest.replacen("manifest_1.json", file, 1);
+      fs::write(dir.join(MANIFEST_FILE), escaping).unwrap();
+      assert!(matches!(LevelPack::load(&dir), Err(PackError::InvalidLevelFile(_))));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(reloaded.unwrap(), "manifest_1");
+  }
+}
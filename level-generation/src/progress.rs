@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+// A snapshot of the clue search, reported once per round of expanding the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchProgress {
+  // Partially solved boards waiting to be expanded.
+  pub queue: usize,
+  // Unknown fields left on the most solved board in the queue.
+  pub best_unknowns: u32,
+  // Clues opened on that board.
+  pub clues: usize,
+  // Search nodes spent so far, shared with the rest of the budget.
+  pub nodes: usize,
+  pub elapsed: Duration,
+}
+
+// Receives progress of long running searches. It is shared between the worker threads of a batch.
+pub trait SearchObserver: Send + Sync {
+  fn on_progress(&self, progress: &SearchProgress);
+}
+
+impl<F: Fn(&SearchProgress) + Send + Sync> SearchObserver for F {
+  fn on_progress(&self, progress: &SearchProgress) {
+    self(progress)
+  }
+}
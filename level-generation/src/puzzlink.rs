@@ -0,0 +1,376 @@
+use std::fmt;
+
+use crate::board::BoardVec;
+use crate::generate::GeneratedLevel;
+use crate::serialize::{LevelData, LevelError};
+use crate::{solve, EmptyPolicy, Field, State};
+
+// Levels as puzz.link (pzprjs) URLs of the closest genre, Snake: `https://puzz.link/p?snake/<width>/<height>/<clues>`.
+//
+// The clues start with pzprjs circles, three fields per base-27 digit, most significant first: 0 for no clue, 1 for a
+// white circle (snake, but not an end) and 2 for a black circle (snake end). The counts outside the board follow,
+// first for the columns, then for the rows, as pzprjs numbers: a hexadecimal digit, `-` or `+` with two or three of
+// them, `.` for a question mark, or `g` to `z` to skip 1 to 20 counts. Its rules differ from ours:
+//
+// - there is no rule about empty regions, so only levels with the `none` empty policy can be written
+// - there are no clues for empty fields, those are left out if the level stays unique without them
+// - the snake must not touch itself diagonally either, so a solution that does cannot be written
+// - row and column counts are not part of our rules, URLs with counts are rejected and written URLs skip all of them
+//
+// With these, a level unique under our rules is also unique under Snake, the other way round it is not necessarily.
+//
+// The layout follows the pzprjs encoders for circles and outside numbers, it has not been compared with URLs exported
+// from puzz.link yet.
+pub const PUZZ_LINK_PREFIX: &str = "https://puzz.link/p?";
+
+const GENRE: &str = "snake";
+
+const DIGITS: &[u8; 27] = b"0123456789abcdefghijklmnopq";
+
+// the most counts a single pzprjs skip character `z` covers
+const MAX_SKIP: usize = 20;
+
+#[derive(Debug)]
+pub enum PuzzLinkError {
+  // the URL is for another pzprjs genre
+  WrongGenre(String),
+  // the URL is not of the form `snake/<width>/<height>/<clues>`
+  Malformed,
+  InvalidSize { width: u32, height: u32 },
+  InvalidCharacter(char),
+  // the URL ends before all fields are read
+  Truncated,
+  // the URL has row or column counts
+  UnsupportedClues,
+  // Snake has no rule about empty regions
+  UnsupportedEmptyPolicy,
+  // Snake forbids the snake to touch itself diagonally, which the solution does at these fields
+  TouchesDiagonally(BoardVec, BoardVec),
+  // the level needs its clues on empty fields to be unique, Snake cannot show them
+  NeedsEmptyClues,
+  NoSolution,
+  // the clues allow more than one solution under our rules, Snake may still rule the others out
+  MultipleSolutions,
+  // the solution cannot be deduced with the given assumption depth
+  NotDeducible,
+  Level(LevelError),
+}
+
+impl fmt::Display for PuzzLinkError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PuzzLinkError::WrongGenre(genre) => {
+        write!(f, "puzz.link genre '{genre}' is not supported, only '{GENRE}' is")
+      }
+      PuzzLinkError::Malformed => write!(f, "puzz.link URL is not of the form {GENRE}/<width>/<height>/<clues>"),
+      PuzzLinkError::InvalidSize { width, height } => {
+        write!(
+          f,
+          "board size {width}x{height} is not supported, both sides must be 2 to 64"
+        )
+      }
+      PuzzLinkError::InvalidCharacter(c) => write!(f, "invalid character '{c}' in puzz.link URL"),
+      PuzzLinkError::Truncated => write!(f, "puzz.link URL is too short"),
+      PuzzLinkError::UnsupportedClues => {
+        write!(f, "puzz.link URL has row or column counts, which are not supported")
+      }
+      PuzzLinkError::UnsupportedEmptyPolicy => {
+        write!(
+          f,
+          "puzz.link has no rule for the sizes of empty regions, only the none policy can be written"
+        )
+      }
+      PuzzLinkError::TouchesDiagonally(a, b) => {
+        write!(
+          f,
+          "the snake touches itself diagonally at {a:?} and {b:?}, which puzz.link forbids"
+        )
+      }
+      PuzzLinkError::NeedsEmptyClues => {
+        write!(
+          f,
+          "the level is not unique without its clues on empty fields, which puzz.link cannot show"
+        )
+      }
+      PuzzLinkError::NoSolution => write!(f, "the clues of the puzz.link URL have no solution"),
+      PuzzLinkError::MultipleSolutions => write!(f, "the clues of the puzz.link URL allow more than one solution"),
+      PuzzLinkError::NotDeducible => {
+        write!(
+          f,
+          "the solution of the puzz.link URL cannot be deduced with this assumption depth"
+        )
+      }
+      PuzzLinkError::Level(err) => write!(f, "{err}"),
+    }
+  }
+}
+
+impl std::error::Error for PuzzLinkError {}
+
+impl From<LevelError> for PuzzLinkError {
+  fn from(err: LevelError) -> Self {
+    PuzzLinkError::Level(err)
+  }
+}
+
+// the first pair of snake fields that touch diagonally without a bend of the snake between them
+fn diagonal_touch(solution: &State) -> Option<(BoardVec, BoardVec)> {
+  let is_snake = |pos| solution.board.get(pos).is_some_and(|field| field.is_snake());
+  solution.board.positions().filter(|&pos| is_snake(pos)).find_map(|pos| {
+    [BoardVec::new(1, 1), BoardVec::new(-1, 1)].into_iter().find_map(|dir| {
+      let other = pos + dir;
+      let bend = is_snake(pos + BoardVec::new(dir.x, 0)) || is_snake(pos + BoardVec::new(0, dir.y));
+      (is_snake(other) && !bend).then_some((pos, other))
+    })
+  })
+}
+
+// The ends and the given snake fields, which must not break the rules.
+fn clue_state(width: u32, height: u32, ends: &[BoardVec], snake: &[BoardVec]) -> Result<State, PuzzLinkError> {
+  let mut state = match ends[..] {
+    [a, b] if a.dist(b) >= 2 => State::new(width, height, a, b, EmptyPolicy::None),
+    [a, b] => return Err(LevelError::SnakeEndsTooClose(a, b).into()),
+    _ => return Err(LevelError::SnakeEndCount(ends.len()).into()),
+  };
+  for &pos in snake {
+    if state.field(pos) == Field::Unknown {
+      state
+        .try_set(pos, Field::Snake)
+        .map_err(|violation| LevelError::RuleViolation {
+          pos,
+          field: Field::Snake,
+          violation,
+        })?;
+    }
+  }
+  Ok(state)
+}
+
+// Skips all `count` counts outside the board.
+fn empty_counts(count: usize) -> String {
+  let skip = |n: usize| (b'f' + n as u8) as char;
+  let mut counts = skip(MAX_SKIP).to_string().repeat(count / MAX_SKIP);
+  if !count.is_multiple_of(MAX_SKIP) {
+    counts.push(skip(count % MAX_SKIP));
+  }
+  counts
+}
+
+// Only skipped counts are supported.
+fn check_empty_counts(counts: &str, count: usize) -> Result<(), PuzzLinkError> {
+  let mut skipped = 0;
+  for c in counts.chars() {
+    match c {
+      'g'..='z' => skipped += (c as u8 - b'f') as usize,
+      '0'..='9' | 'a'..='f' | '-' | '+' | '.' => return Err(PuzzLinkError::UnsupportedClues),
+      _ => return Err(PuzzLinkError::InvalidCharacter(c)),
+    }
+  }
+  if skipped > count {
+    return Err(PuzzLinkError::Malformed);
+  }
+  Ok(())
+}
+
+impl LevelData {
+  pub fn to_puzz_link(&self) -> Result<String, PuzzLinkError> {
+    if !matches!(self.empty_policy()?, EmptyPolicy::None) {
+      return Err(PuzzLinkError::UnsupportedEmptyPolicy);
+    }
+    let solution = self.solution()?;
+    if let Some((a, b)) = diagonal_touch(&solution) {
+      return Err(PuzzLinkError::TouchesDiagonally(a, b));
+    }
+
+    let (width, height) = (self.width(), self.height());
+    let ends = solution.snake_ends();
+    let snake: Vec<BoardVec> = self
+      .initial_open()
+      .iter()
+      .copied()
+      .filter(|&pos| solution.field(pos) == Field::Snake)
+      .collect();
+    let mut results = Vec::new();
+    solve(clue_state(width, height, ends, &snake)?, &mut results, 2);
+    if results.len() != 1 {
+      return Err(PuzzLinkError::NeedsEmptyClues);
+    }
+
+    let circles: Vec<usize> = solution
+      .board
+      .positions()
+      .map(|pos| match () {
+        _ if ends.contains(&pos) => 2,
+        _ if snake.contains(&pos) => 1,
+        _ => 0,
+      })
+      .collect();
+    let clues: String = circles
+      .chunks(3)
+      .map(|chunk| {
+        let digit = (0..3).fold(0, |n, i| n * 3 + chunk.get(i).copied().unwrap_or(0));
+        DIGITS[digit] as char
+      })
+      .collect();
+    let counts = empty_counts((width + height) as usize);
+    Ok(format!("{PUZZ_LINK_PREFIX}{GENRE}/{width}/{height}/{clues}{counts}"))
+  }
+
+  // Accepts the URL with any host, or only the part after `?`. The URL does not say how hard the level is meant to
+  // be, it is only read if the solution can be deduced with at most `max_assume_depth`.
+  pub fn from_puzz_link(url: &str, max_assume_depth: usize) -> Result<Self, PuzzLinkError> {
+    let url = url.trim();
+    let query = url.split_once('?').map_or(url, |(_, query)| query);
+    let parts: Vec<&str> = query.split('/').collect();
+    if parts[0] != GENRE {
+      return Err(PuzzLinkError::WrongGenre(parts[0].to_string()));
+    }
+    let [_, width, height, clues] = parts[..] else {
+      return Err(PuzzLinkError::Malformed);
+    };
+    let (Ok(width), Ok(height)) = (width.parse::<u32>(), height.parse::<u32>()) else {
+      return Err(PuzzLinkError::Malformed);
+    };
+    if !(2..=64).contains(&width) || !(2..=64).contains(&height) {
+      return Err(PuzzLinkError::InvalidSize { width, height });
+    }
+
+    if let Some(c) = clues.chars().find(|c| !c.is_ascii()) {
+      return Err(PuzzLinkError::InvalidCharacter(c));
+    }
+    let len = (width * height) as usize;
+    let Some((clues, counts)) = clues.split_at_checked(len.div_ceil(3)) else {
+      return Err(PuzzLinkError::Truncated);
+    };
+    check_empty_counts(counts, (width + height) as usize)?;
+
+    let mut circles = Vec::new();
+    for c in clues.chars() {
+      let digit = DIGITS
+        .iter()
+        .position(|&d| d as char == c)
+        .ok_or(PuzzLinkError::InvalidCharacter(c))?;
+      circles.extend([digit / 9, digit / 3 % 3, digit % 3]);
+    }
+    if circles[len..].iter().any(|&c| c != 0) {
+      return Err(PuzzLinkError::Malformed);
+    }
+
+    let positions = State::new_empty(width, height, EmptyPolicy::None).board.positions();
+    let (mut ends, mut snake) = (Vec::new(), Vec::new());
+    for (pos, circle) in positions.zip(circles) {
+      match circle {
+        2 => ends.push(pos),
+        1 => snake.push(pos),
+        _ => {}
+      }
+    }
+    let puzzle = clue_state(width, height, &ends, &snake)?;
+
+    let mut results = Vec::new();
+    solve(puzzle.clone(), &mut results, 2);
+    let solution = match &results[..] {
+      [] => return Err(PuzzLinkError::NoSolution),
+      [solution] => solution,
+      _ => return Err(PuzzLinkError::MultipleSolutions),
+    };
+    if let Some((a, b)) = diagonal_touch(solution) {
+      return Err(PuzzLinkError::TouchesDiagonally(a, b));
+    }
+
+    let initial = State::new(width, height, ends[0], ends[1], EmptyPolicy::None);
+    let level = GeneratedLevel::from_clues(&initial, solution, snake, max_assume_depth)
+      .ok_or(PuzzLinkError::NotDeducible)?;
+    Ok(level.level_data())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{empty_counts, PuzzLinkError};
+  use crate::board::BoardVec;
+  use crate::generate::GeneratedLevel;
+  use crate::serialize::LevelData;
+  use crate::test_util::example_level;
+  use crate::{EmptyPolicy, State};
+
+  fn level(solution: &str, snake_clues: &[(i32, i32)]) -> LevelData {
+    let solution: State = format!("policy: none\n{solution}").parse().unwrap();
+    let ends = solution.snake_ends();
+    let initial = State::new(5, 5, ends[0], ends[1], EmptyPolicy::None);
+    let clues = snake_clues.iter().map(|&(x, y)| BoardVec::new(x, y)).collect();
+    GeneratedLevel::from_clues(&initial, &solution, clues, 2)
+      .unwrap()
+      .level_data()
+  }
+
+  #[test]
+  fn test_puzz_link() {
+    let level = level(
+      "
+      |X+++.|
+      |...+.|
+      |.+++.|
+      |.+...|
+      |.+++X|
+      ",
+      &[(3, 1), (2, 2), (1, 3), (2, 4)],
+    );
+    let url = level.to_puzz_link().unwrap();
+    assert_eq!(url, "https://puzz.link/p?snake/5/5/i0109303ip");
+
+    // with the count section split into skips, or without it
+    for url in [
+      url.as_str(),
+      "snake/5/5/i0109303iki",
+      "http://pzv.jp/p.html?snake/5/5/i0109303i",
+    ] {
+      let decoded = LevelData::from_puzz_link(url, 2).unwrap();
+      assert_eq!(decoded.solution().unwrap(), level.solution().unwrap());
+      assert_eq!(decoded.puzzle().unwrap(), level.puzzle().unwrap());
+    }
+    assert!(matches!(
+      LevelData::from_puzz_link("snake/5/5/i0109303ij3k", 2),
+      Err(PuzzLinkError::UnsupportedClues)
+    ));
+    assert!(matches!(
+      LevelData::from_puzz_link("snake/5/5/i0109303iq", 2),
+      Err(PuzzLinkError::Malformed)
+    ));
+    assert_eq!(empty_counts(44), "zzj");
+    assert!(matches!(
+      LevelData::from_puzz_link("snake/5/5/i0109", 2),
+      Err(PuzzLinkError::Truncated)
+    ));
+    assert!(matches!(
+      LevelData::from_puzz_link("mashu/5/5/i0109303i", 2),
+      Err(PuzzLinkError::WrongGenre(_))
+    ));
+    assert!(matches!(
+      LevelData::from_puzz_link("snake/5/5/i0000000i", 2),
+      Err(PuzzLinkError::MultipleSolutions)
+    ));
+  }
+
+  #[test]
+  fn test_puzz_link_unsupported() {
+    assert!(matches!(
+      example_level().to_puzz_link(),
+      Err(PuzzLinkError::UnsupportedEmptyPolicy)
+    ));
+    let touching = level(
+      "
+      |.+++.|
+      |X+.+.|
+      |..++.|
+      |..+..|
+      |..X..|
+      ",
+      &[(1, 1), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (2, 2), (2, 3)],
+    );
+    assert!(matches!(
+      touching.to_puzz_link(),
+      Err(PuzzLinkError::TouchesDiagonally(..))
+    ));
+  }
+}
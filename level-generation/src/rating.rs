@@ -0,0 +1,198 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Deduction, Field, SolveStep, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Tier {
+  Easy,
+  Medium,
+  Hard,
+  Expert,
+}
+
+impl Tier {
+  pub const ALL: [Tier; 4] = [Tier::Easy, Tier::Medium, Tier::Hard, Tier::Expert];
+
+  // The bands split the scores of a generated sample into quarters: 90 levels, 15 each of 5x5, 6x6 and 7x7 with
+  // assumption depth 1 and 2 (`generate --seed 101 --count 15`), had the quartiles 52, 56 and 65. Regenerate such a
+  // sample and move the bands along when the score weights or the rated trace change.
+  pub fn from_score(score: u32) -> Self {
+    match score {
+      0..=51 => Tier::Easy,
+      52..=55 => Tier::Medium,
+      56..=64 => Tier::Hard,
+      _ => Tier::Expert,
+    }
+  }
+}
+
+impl fmt::Display for Tier {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Tier::Easy => write!(f, "easy"),
+      Tier::Medium => write!(f, "medium"),
+      Tier::Hard => write!(f, "hard"),
+      Tier::Expert => write!(f, "expert"),
+    }
+  }
+}
+
+impl FromStr for Tier {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Tier::ALL
+      .into_iter()
+      .find(|tier| tier.to_string().eq_ignore_ascii_case(s))
+      .ok_or_else(|| format!("unknown tier `{s}`"))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Difficulty {
+  pub score: u32,
+  pub tier: Tier,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+  pub clues: usize,
+  pub obvious: usize,
+  // number of deductions that needed an assumption of depth `i + 1`
+  pub assumptions: Vec<usize>,
+  pub score: u32,
+  pub tier: Tier,
+}
+
+impl Rating {
+  pub fn difficulty(&self) -> Difficulty {
+    Difficulty {
+      score: self.score,
+      tier: self.tier,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifficultyTarget {
+  Tier(Tier),
+  Score(RangeInclusive<u32>),
+}
+
+impl DifficultyTarget {
+  pub fn matches(&self, rating: &Rating) -> bool {
+    match self {
+      DifficultyTarget::Tier(tier) => rating.tier == *tier,
+      DifficultyTarget::Score(range) => range.contains(&rating.score),
+    }
+  }
+}
+
+fn obvious_choices(state: &State) -> usize {
+  state
+    .board
+    .positions()
+    .filter(|&pos| state.field(pos) == Field::Unknown)
+    .filter(|&pos| !state.snake_allowed(pos) || !state.empty_allowed(pos))
+    .count()
+}
+
+// `puzzle` is the board with all clues opened, `steps` its canonical trace (see `canonical_trace`). Other orders of
+// the same deductions leave the player more or less choice along the way and would give other scores.
+pub fn rate(puzzle: &State, steps: &[SolveStep]) -> Rating {
+  let cells = (puzzle.width() * puzzle.height()) as usize;
+  let clues = cells - puzzle.unknowns() as usize;
+
+  let mut state = puzzle.clone();
+  let mut obvious = 0;
+  let mut assumptions = Vec::new();
+  let mut assumption_weight = 0;
+  let mut narrow_steps = 0;
+
+  for step in steps {
+    // steps where at most one field followed without assumption left the player no choice
+    if obvious_choices(&state) <= 1 {
+      narrow_steps += 1;
+    }
+
+    match step.deduction {
+      Deduction::Obvious => obvious += 1,
+      Deduction::Assumption(depth) => {
+        if assumptions.len() < depth {
+          assumptions.resize(depth, 0);
+        }
+        assumptions[depth - 1] += 1;
+        assumption_weight += depth * depth;
+      }
+    }
+    state.set(step.pos, step.field);
+  }
+
+  let steps_count = steps.len().max(1) as f64;
+
+  // every part lies between 0 and its weight, so that the score stays roughly in 0..=100
+  let assumption_part = 50.0 * (assumption_weight as f64 / steps_count).min(1.0);
+  let clue_part = 25.0 * (1.0 - clues as f64 / cells as f64);
+  let narrow_part = 25.0 * (narrow_steps as f64 / steps_count);
+  let score = (assumption_part + clue_part + narrow_part).round() as u32;
+
+  Rating {
+    clues,
+    obvious,
+    assumptions,
+    score,
+    tier: Tier::from_score(score),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{rate, Tier};
+  use crate::generate::GeneratedLevel;
+  use crate::serialize::LevelData;
+  use crate::test_util::example_level;
+
+  #[test]
+  fn test_rate_level() {
+    let data = example_level();
+    let level = GeneratedLevel::from_level_data(&data).unwrap();
+    let rating = rate(&level.puzzle(), &level.steps);
+
+    assert_eq!(rating.clues, 8);
+    assert_eq!(rating.obvious + rating.assumptions.iter().sum::<usize>(), 41);
+    assert_eq!(rating.tier, Tier::from_score(rating.score));
+  }
+
+  #[test]
+  fn test_rating_ignores_move_order() {
+    let level = GeneratedLevel::from_level_data(&example_level()).unwrap();
+    let mut moves = level.moves();
+    moves.reverse();
+    let reversed = LevelData::new(
+      &level.solution,
+      level.initial_open.clone(),
+      moves,
+      level.max_assume_depth,
+    );
+
+    let reloaded = GeneratedLevel::from_level_data(&reversed).unwrap();
+    assert_eq!(reloaded.steps, level.steps);
+    assert_eq!(reloaded.rating(), level.rating());
+  }
+
+  #[test]
+  fn test_tiers_ordered() {
+    let tiers: Vec<Tier> = (0..100).map(Tier::from_score).collect();
+    assert!(tiers.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(tiers.first(), Some(&Tier::Easy));
+    assert_eq!(tiers.last(), Some(&Tier::Expert));
+
+    for tier in Tier::ALL {
+      assert_eq!(tier.to_string().parse(), Ok(tier));
+    }
+  }
+}
@@ -1,15 +1,25 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, fs, io};
 
 use serde::{Deserialize, Serialize};
 
 use crate::board::BoardVec;
-use crate::{EmptyPolicy, State};
+use crate::hint::HintRule;
+use crate::rating::Difficulty;
+use crate::{EmptyPolicy, Field, SnakeConnectedness, State, Symmetry, Violation};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SerializableEmptyPolicy {
   None,
   Fix { fix_size: usize },
   Ascending { top: usize },
+  Range { min: usize, max: usize },
+  Sizes { sizes: Vec<usize> },
+  Distinct,
+  Odd,
+  Even,
 }
 
 impl SerializableEmptyPolicy {
@@ -18,31 +28,228 @@ impl SerializableEmptyPolicy {
       EmptyPolicy::None => Self::None,
       EmptyPolicy::Fix(fix_size) => Self::Fix { fix_size: *fix_size },
       EmptyPolicy::Ascending(nums, _) => Self::Ascending { top: nums.len() },
+      EmptyPolicy::Range(min, max) => Self::Range { min: *min, max: *max },
+      EmptyPolicy::Sizes(sizes, _) => Self::Sizes { sizes: sizes.clone() },
+      EmptyPolicy::Distinct(_) => Self::Distinct,
+      EmptyPolicy::Odd => Self::Odd,
+      EmptyPolicy::Even => Self::Even,
+    }
+  }
+
+  pub fn to_empty_policy(&self, width: u32, height: u32) -> Result<EmptyPolicy, LevelError> {
+    match self {
+      Self::None => Ok(EmptyPolicy::None),
+      &Self::Fix { fix_size } => Ok(EmptyPolicy::Fix(fix_size)),
+      Self::Ascending { .. } => Ok(EmptyPolicy::new_ascending(width, height)),
+      &Self::Range { min, max } if min > max => Err(LevelError::UnsupportedEmptyPolicy),
+      &Self::Range { min, max } => Ok(EmptyPolicy::Range(min, max)),
+      Self::Sizes { sizes } => Ok(EmptyPolicy::new_sizes(sizes.clone())),
+      Self::Distinct => Ok(EmptyPolicy::new_distinct()),
+      Self::Odd => Ok(EmptyPolicy::Odd),
+      Self::Even => Ok(EmptyPolicy::Even),
+    }
+  }
+
+  // sizes may be listed in any order, the solver keeps them sorted
+  fn normalized(&self) -> Self {
+    match self {
+      Self::Sizes { sizes } => {
+        let mut sizes = sizes.clone();
+        sizes.sort_unstable();
+        Self::Sizes { sizes }
+      }
+      p => p.clone(),
+    }
+  }
+}
+
+// The compact form used in headers of board drawings, e.g. `fix 3`, `range 2 5` or `sizes 1,2,3`.
+impl fmt::Display for SerializableEmptyPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::None => write!(f, "none"),
+      Self::Fix { fix_size } => write!(f, "fix {fix_size}"),
+      Self::Ascending { .. } => write!(f, "ascending"),
+      Self::Range { min, max } => write!(f, "range {min} {max}"),
+      Self::Sizes { sizes } => {
+        let sizes: Vec<String> = sizes.iter().map(|size| size.to_string()).collect();
+        write!(f, "sizes {}", sizes.join(","))
+      }
+      Self::Distinct => write!(f, "distinct"),
+      Self::Odd => write!(f, "odd"),
+      Self::Even => write!(f, "even"),
     }
   }
 }
 
-#[derive(Debug, Serialize)]
+impl FromStr for SerializableEmptyPolicy {
+  type Err = LevelError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || LevelError::InvalidPolicy(s.to_string());
+    let mut words = s.split_whitespace();
+    let name = words.next().ok_or_else(invalid)?;
+    let numbers: Vec<usize> = words
+      .flat_map(|word| word.split(','))
+      .filter(|n| !n.is_empty())
+      .map(|n| n.parse().map_err(|_| invalid()))
+      .collect::<Result<_, _>>()?;
+
+    match (name.to_ascii_lowercase().as_str(), &numbers[..]) {
+      ("none", []) => Ok(Self::None),
+      ("fix", &[fix_size]) => Ok(Self::Fix { fix_size }),
+      ("ascending", []) => Ok(Self::Ascending { top: 0 }),
+      ("range", &[min, max]) => Ok(Self::Range { min, max }),
+      ("sizes", [_, ..]) => Ok(Self::Sizes { sizes: numbers.clone() }),
+      ("distinct", []) => Ok(Self::Distinct),
+      ("odd", []) => Ok(Self::Odd),
+      ("even", []) => Ok(Self::Even),
+      _ => Err(invalid()),
+    }
+  }
+}
+
+// Version 1 levels have no version field and store bare move positions with the last move first.
+// Version 2 stores every move in solve order together with its field and the rule that forced it.
+pub const LEVEL_FORMAT_VERSION: u32 = 2;
+
+fn legacy_version() -> u32 {
+  1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveData {
+  pub pos: BoardVec,
+  pub field: Field,
+  pub rule: HintRule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Moves {
+  Legacy(Vec<BoardVec>),
+  Steps(Vec<MoveData>),
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+  Io(io::Error),
+  Json(serde_json::Error),
+  MissingFieldChar(&'static str),
+  WrongLineCount {
+    expected: usize,
+    got: usize,
+  },
+  WrongLineLength {
+    line: usize,
+    expected: usize,
+    got: usize,
+  },
+  UnknownFieldChar {
+    pos: BoardVec,
+    c: char,
+  },
+  SnakeEndCount(usize),
+  SnakeEndsTooClose(BoardVec, BoardVec),
+  RuleViolation {
+    pos: BoardVec,
+    field: Field,
+    violation: Violation,
+  },
+  SnakeNotConnected,
+  PolicyViolated {
+    declared: SerializableEmptyPolicy,
+    found: SerializableEmptyPolicy,
+  },
+  OutOfBounds(BoardVec),
+  UndeducibleMove(BoardVec),
+  // the clues are not symmetric although the level declares a symmetry, or the symmetry does not fit the board
+  AsymmetricClues(Symmetry),
+  UnsupportedEmptyPolicy,
+  UnsupportedVersion(u32),
+  InvalidPolicy(String),
+}
+
+impl fmt::Display for LevelError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LevelError::Io(err) => write!(f, "could not read level: {err}"),
+      LevelError::Json(err) => write!(f, "invalid level json: {err}"),
+      LevelError::MissingFieldChar(name) => write!(f, "no character defined for field '{name}'"),
+      LevelError::WrongLineCount { expected, got } => {
+        write!(f, "wrong line count. Got {got}, expected {expected}")
+      }
+      LevelError::WrongLineLength { line, expected, got } => {
+        write!(f, "wrong length of line {line}. Got {got}, expected {expected}")
+      }
+      LevelError::UnknownFieldChar { pos, c } => write!(f, "unknown field character '{c}' at {pos:?}"),
+      LevelError::SnakeEndCount(n) => write!(f, "expected 2 snake ends, got {n}"),
+      LevelError::SnakeEndsTooClose(a, b) => write!(f, "snake ends {a:?} and {b:?} are too close"),
+      LevelError::RuleViolation { pos, field, violation } => {
+        write!(f, "{field:?} at {pos:?} violates the rules: {violation}")
+      }
+      LevelError::SnakeNotConnected => write!(f, "snake does not connect both ends"),
+      LevelError::PolicyViolated { declared, found } => write!(
+        f,
+        "empty regions do not satisfy the declared policy {declared:?}, they match {found:?}"
+      ),
+      LevelError::OutOfBounds(pos) => write!(f, "position {pos:?} is outside of the board"),
+      LevelError::UndeducibleMove(pos) => write!(f, "move at {pos:?} cannot be deduced"),
+      LevelError::AsymmetricClues(symmetry) => write!(f, "the clues do not have the declared {symmetry} symmetry"),
+      LevelError::UnsupportedEmptyPolicy => write!(f, "unsupported empty policy"),
+      LevelError::UnsupportedVersion(version) => write!(
+        f,
+        "unsupported level format version {version}, at most {LEVEL_FORMAT_VERSION} is supported"
+      ),
+      LevelError::InvalidPolicy(policy) => write!(f, "invalid empty policy '{policy}'"),
+    }
+  }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+  fn from(err: io::Error) -> Self {
+    LevelError::Io(err)
+  }
+}
+
+impl From<serde_json::Error> for LevelError {
+  fn from(err: serde_json::Error) -> Self {
+    LevelError::Json(err)
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LevelData {
+  #[serde(default = "legacy_version")]
+  version: u32,
   width: usize,
   height: usize,
   max_assumption_depth: usize,
-  fields: HashMap<String, char>,
+  fields: BTreeMap<String, char>,
   level: Vec<String>,
   initial_open: Vec<BoardVec>,
-  moves: Vec<BoardVec>,
+  moves: Moves,
   author: String,
   empty_policy: SerializableEmptyPolicy,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  seed: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  difficulty: Option<Difficulty>,
+  // the symmetry the clues were placed with, if any
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  symmetry: Option<Symmetry>,
 }
 
 impl LevelData {
   pub fn new(
     solution: &State,
     mut initial_open: Vec<BoardVec>,
-    moves: Vec<BoardVec>,
+    moves: Vec<MoveData>,
     max_assumption_depth: usize,
   ) -> Self {
-    let mut fields = HashMap::new();
+    let mut fields = BTreeMap::new();
     fields.insert("snake-head".to_string(), 'X');
     fields.insert("snake-body".to_string(), '+');
     fields.insert("empty".to_string(), '.');
@@ -64,15 +271,481 @@ impl LevelData {
     initial_open.extend(solution.snake_ends.iter());
 
     Self {
+      version: LEVEL_FORMAT_VERSION,
       height: solution.height() as usize,
       width: solution.width() as usize,
       max_assumption_depth,
       fields,
       level,
       initial_open,
-      moves,
+      moves: Moves::Steps(moves),
       author: "Tobias K.".to_string(),
       empty_policy: SerializableEmptyPolicy::new(&solution.empty_policy),
+      seed: None,
+      difficulty: None,
+      symmetry: None,
+    }
+  }
+
+  pub fn with_seed(self, seed: Option<u64>) -> Self {
+    Self { seed, ..self }
+  }
+
+  pub fn from_json(json: &str) -> Result<Self, LevelError> {
+    let level: Self = serde_json::from_str(json)?;
+    if level.version > LEVEL_FORMAT_VERSION {
+      return Err(LevelError::UnsupportedVersion(level.version));
+    }
+    Ok(level)
+  }
+
+  pub fn version(&self) -> u32 {
+    self.version
+  }
+
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+    Self::from_json(&fs::read_to_string(path)?)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width as u32
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height as u32
+  }
+
+  pub fn max_assumption_depth(&self) -> usize {
+    self.max_assumption_depth
+  }
+
+  pub fn initial_open(&self) -> &[BoardVec] {
+    &self.initial_open
+  }
+
+  // positions of the moves in solve order
+  pub fn moves(&self) -> Vec<BoardVec> {
+    match &self.moves {
+      Moves::Legacy(moves) => moves.iter().rev().copied().collect(),
+      Moves::Steps(moves) => moves.iter().map(|m| m.pos).collect(),
+    }
+  }
+
+  pub fn move_data(&self) -> Option<&[MoveData]> {
+    match &self.moves {
+      Moves::Legacy(_) => None,
+      Moves::Steps(moves) => Some(moves),
+    }
+  }
+
+  pub fn with_difficulty(self, difficulty: Option<Difficulty>) -> Self {
+    Self { difficulty, ..self }
+  }
+
+  pub fn difficulty(&self) -> Option<Difficulty> {
+    self.difficulty
+  }
+
+  pub fn seed(&self) -> Option<u64> {
+    self.seed
+  }
+
+  pub fn with_symmetry(self, symmetry: Option<Symmetry>) -> Self {
+    Self { symmetry, ..self }
+  }
+
+  pub fn symmetry(&self) -> Option<Symmetry> {
+    self.symmetry
+  }
+
+  pub fn with_author(self, author: String) -> Self {
+    Self { author, ..self }
+  }
+
+  pub fn author(&self) -> &str {
+    &self.author
+  }
+
+  pub fn empty_policy(&self) -> Result<EmptyPolicy, LevelError> {
+    self.empty_policy.to_empty_policy(self.width(), self.height())
+  }
+
+  fn field_char(&self, name: &'static str) -> Result<char, LevelError> {
+    self.fields.get(name).copied().ok_or(LevelError::MissingFieldChar(name))
+  }
+
+  fn solution_fields(&self) -> Result<Vec<(BoardVec, Field)>, LevelError> {
+    let snake_head = self.field_char("snake-head")?;
+    let snake_body = self.field_char("snake-body")?;
+    let empty = self.field_char("empty")?;
+
+    if self.level.len() != self.height {
+      return Err(LevelError::WrongLineCount {
+        expected: self.height,
+        got: self.level.len(),
+      });
+    }
+
+    let mut fields = Vec::with_capacity(self.width * self.height);
+    for (y, line) in self.level.iter().enumerate() {
+      let len = line.chars().count();
+      if len != self.width {
+        return Err(LevelError::WrongLineLength {
+          line: y,
+          expected: self.width,
+          got: len,
+        });
+      }
+
+      for (x, c) in line.chars().enumerate() {
+        let pos = BoardVec::new(x as i32, y as i32);
+        let field = if c == snake_head {
+          Field::SnakeEnd
+        } else if c == snake_body {
+          Field::Snake
+        } else if c == empty {
+          Field::Empty
+        } else {
+          return Err(LevelError::UnknownFieldChar { pos, c });
+        };
+        fields.push((pos, field));
+      }
+    }
+
+    Ok(fields)
+  }
+
+  fn snake_ends_state(&self, fields: &[(BoardVec, Field)]) -> Result<State, LevelError> {
+    let ends: Vec<BoardVec> = fields
+      .iter()
+      .filter(|(_, field)| *field == Field::SnakeEnd)
+      .map(|&(pos, _)| pos)
+      .collect();
+
+    match ends[..] {
+      [a, b] if a.dist(b) >= 2 => Ok(State::new(self.width(), self.height(), a, b, self.empty_policy()?)),
+      [a, b] => Err(LevelError::SnakeEndsTooClose(a, b)),
+      _ => Err(LevelError::SnakeEndCount(ends.len())),
+    }
+  }
+
+  fn set_checked(state: &mut State, pos: BoardVec, field: Field) -> Result<(), LevelError> {
+    state
+      .try_set(pos, field)
+      .map_err(|violation| LevelError::RuleViolation { pos, field, violation })
+  }
+
+  pub fn solution(&self) -> Result<State, LevelError> {
+    let fields = self.solution_fields()?;
+    let mut state = self.snake_ends_state(&fields)?;
+
+    for &(pos, field) in fields.iter() {
+      if field != Field::SnakeEnd {
+        Self::set_checked(&mut state, pos, field)?;
+      }
+    }
+
+    if state.is_snake_connected() != SnakeConnectedness::Connected {
+      return Err(LevelError::SnakeNotConnected);
+    }
+
+    let found = SerializableEmptyPolicy::new(state.empty_policy());
+    if !state.empty_policy().is_still_possible(0) || found != self.empty_policy.normalized() {
+      return Err(LevelError::PolicyViolated {
+        declared: self.empty_policy.clone(),
+        found,
+      });
+    }
+
+    Ok(state)
+  }
+
+  pub fn puzzle(&self) -> Result<State, LevelError> {
+    let fields = self.solution_fields()?;
+    let mut state = self.snake_ends_state(&fields)?;
+
+    for &pos in self.initial_open.iter() {
+      let index = state.board.pos_to_index(pos).ok_or(LevelError::OutOfBounds(pos))?;
+      let (_, field) = fields[index];
+      if state.field(pos) == Field::Unknown {
+        Self::set_checked(&mut state, pos, field)?;
+      }
+    }
+
+    Ok(state)
+  }
+
+  pub fn states(&self) -> Result<(State, State), LevelError> {
+    Ok((self.solution()?, self.puzzle()?))
+  }
+}
+
+// Reads the board drawings printed by `State`'s Debug impl as well as the `level` rows of level files:
+//
+// policy: fix 3
+// x-----x
+// |X+  ·|
+// |··+ X|
+// x-----x
+//
+// `X` is a snake end, `+` snake, `.` or `·` empty, and space or a digit (the Debug output shows the number of
+// unknown neighbours) unknown. The frame and the policy header are optional, boards without header have no rule
+// for empty regions. Rows without frame are trimmed, so they cannot start or end with an unknown field.
+impl State {
+  pub fn from_ascii(s: &str) -> Result<Self, LevelError> {
+    let mut policy = SerializableEmptyPolicy::None;
+    let mut rows: Vec<Vec<char>> = Vec::new();
+    for line in s.lines() {
+      let line = line.trim();
+      let is_frame = line.len() >= 2 && line.starts_with('x') && line.ends_with('x') && {
+        line[1..line.len() - 1].chars().all(|c| c == '-')
+      };
+      if line.is_empty() || is_frame {
+        continue;
+      }
+
+      if let Some(header) = line.strip_prefix("policy:") {
+        policy = header.parse()?;
+      } else if let Some(row) = line.strip_prefix('|').and_then(|line| line.strip_suffix('|')) {
+        rows.push(row.chars().collect());
+      } else {
+        rows.push(line.chars().collect());
+      }
+    }
+
+    let width = rows.first().map_or(0, Vec::len);
+    let mut fields = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+      if row.len() != width {
+        return Err(LevelError::WrongLineLength {
+          line: y,
+          expected: width,
+          got: row.len(),
+        });
+      }
+
+      for (x, &c) in row.iter().enumerate() {
+        let pos = BoardVec::new(x as i32, y as i32);
+        let field = match c {
+          'X' => Field::SnakeEnd,
+          '+' => Field::Snake,
+          '.' | '·' => Field::Empty,
+          ' ' | '0'..='9' => Field::Unknown,
+          c => return Err(LevelError::UnknownFieldChar { pos, c }),
+        };
+        fields.push((pos, field));
+      }
+    }
+
+    let (width, height) = (width as u32, rows.len() as u32);
+    let ends: Vec<BoardVec> = fields
+      .iter()
+      .filter(|(_, field)| *field == Field::SnakeEnd)
+      .map(|&(pos, _)| pos)
+      .collect();
+    let mut state = match ends[..] {
+      [a, b] if a.dist(b) >= 2 => State::new(width, height, a, b, policy.to_empty_policy(width, height)?),
+      [a, b] => return Err(LevelError::SnakeEndsTooClose(a, b)),
+      _ => return Err(LevelError::SnakeEndCount(ends.len())),
+    };
+
+    for (pos, field) in fields {
+      if field != Field::Unknown && field != Field::SnakeEnd {
+        LevelData::set_checked(&mut state, pos, field)?;
+      }
+    }
+
+    Ok(state)
+  }
+}
+
+impl FromStr for State {
+  type Err = LevelError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    State::from_ascii(s)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{LevelData, LevelError, SerializableEmptyPolicy, LEVEL_FORMAT_VERSION};
+  use crate::board::BoardVec;
+  use crate::generate::{try_generate, GenerateOptions, GeneratedLevel};
+  use crate::test_util::{example_level, LEVEL};
+  use crate::verify::verify_level;
+  use crate::{solve, EmptyPolicy, Field, State};
+
+  #[test]
+  fn test_load_level() {
+    let level = example_level();
+    let (solution, puzzle) = level.states().unwrap();
+
+    assert_eq!(solution.unknowns(), 0);
+    assert_eq!(puzzle.field(BoardVec::new(0, 5)), Field::SnakeEnd);
+    assert_eq!(puzzle.field(BoardVec::new(1, 1)), Field::Empty);
+    assert_eq!(puzzle.unknowns() as usize, 7 * 7 - level.initial_open().len());
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let level = example_level();
+    assert_eq!(level.version(), 1);
+    assert!(level.move_data().is_none());
+
+    let data = GeneratedLevel::from_level_data(&level).unwrap().level_data();
+    let json = serde_json::to_string(&data).unwrap();
+    let reloaded = LevelData::from_json(&json).unwrap();
+
+    assert_eq!(reloaded.version(), LEVEL_FORMAT_VERSION);
+    assert_eq!(reloaded.solution().unwrap(), level.solution().unwrap());
+    assert_eq!(reloaded.puzzle().unwrap(), level.puzzle().unwrap());
+
+    let moves = reloaded.move_data().unwrap();
+    let solution = reloaded.solution().unwrap();
+    assert_eq!(moves.len(), level.moves().len());
+    assert!(moves.iter().all(|m| m.field == solution.field(m.pos)));
+  }
+
+  #[test]
+  fn test_policies() {
+    let options = GenerateOptions {
+      width: 5,
+      height: 5,
+      empty_policy: EmptyPolicy::Fix(3),
+      max_assume_depth: 1,
+      symmetry: None,
+    };
+    let level = (0..).find_map(|seed| try_generate(&options, seed)).unwrap();
+    let json = serde_json::to_string(&level.level_data()).unwrap();
+    let reloaded = LevelData::from_json(&json).unwrap();
+
+    assert_eq!(reloaded.empty_policy().unwrap(), EmptyPolicy::Fix(3));
+    assert_eq!(reloaded.solution().unwrap(), level.solution);
+    verify_level(&reloaded).unwrap();
+
+    let none = LEVEL.replacen("{\n    \"Ascending\": {\n      \"top\": 6\n    }\n  }", "\"None\"", 1);
+    let none = LevelData::from_json(&none).unwrap();
+    assert_eq!(none.empty_policy().unwrap(), EmptyPolicy::None);
+    assert_eq!(none.solution().unwrap().empty_policy(), &EmptyPolicy::None);
+
+    let wrong_top = LEVEL.replacen("\"top\": 6", "\"top\": 5", 1);
+    assert!(matches!(
+      LevelData::from_json(&wrong_top).unwrap().solution(),
+      Err(LevelError::PolicyViolated { .. })
+    ));
+  }
+
+  #[test]
+  fn test_policy_family() {
+    let policies = [
+      EmptyPolicy::Range(1, 4),
+      EmptyPolicy::new_sizes(vec![3, 2, 1, 2]),
+      EmptyPolicy::new_distinct(),
+      EmptyPolicy::Odd,
+      EmptyPolicy::Even,
+    ];
+    for policy in policies {
+      let state = State::new(5, 5, BoardVec::new(0, 0), BoardVec::new(0, 4), policy.clone());
+      let mut results = Vec::new();
+      solve(state, &mut results, 5);
+      assert!(!results.is_empty(), "{policy:?}");
+
+      for solution in results {
+        let json = serde_json::to_string(&LevelData::new(&solution, Vec::new(), Vec::new(), 0)).unwrap();
+        let reloaded = LevelData::from_json(&json).unwrap();
+        assert_eq!(reloaded.empty_policy().unwrap(), policy);
+        assert_eq!(reloaded.solution().unwrap(), solution);
+      }
+    }
+
+    let sizes = |sizes| {
+      LEVEL.replacen(
+        "\"Ascending\": {\n      \"top\": 6",
+        &format!("\"Sizes\": {{\"sizes\": {sizes}"),
+        1,
+      )
+    };
+    let unsorted = LevelData::from_json(&sizes("[6, 1, 5, 2, 4, 3]"))
+      .unwrap()
+      .solution()
+      .unwrap();
+    let ascending = example_level().solution().unwrap();
+    assert!(ascending
+      .board
+      .positions()
+      .all(|pos| unsorted.field(pos) == ascending.field(pos)));
+    assert!(LevelData::from_json(&sizes("[6, 1]")).unwrap().solution().is_err());
+  }
+
+  #[test]
+  fn test_future_version() {
+    let json = LEVEL.replacen("\"version\": 1,", "\"version\": 99,", 1);
+    assert!(matches!(
+      LevelData::from_json(&json),
+      Err(LevelError::UnsupportedVersion(99))
+    ));
+  }
+
+  #[test]
+  fn test_malformed() {
+    let broken = LEVEL.replacen("\"+++++..\"", "\"+++++.\"", 1);
+    let level = LevelData::from_json(&broken).unwrap();
+    assert!(matches!(
+      level.solution(),
+      Err(LevelError::WrongLineLength { line: 0, .. })
+    ));
+
+    let broken = LEVEL.replacen("\"+++++..\"", "\"+++++.+\"", 1);
+    let level = LevelData::from_json(&broken).unwrap();
+    assert!(matches!(level.solution(), Err(LevelError::RuleViolation { .. })));
+
+    assert!(matches!(LevelData::from_json("{}"), Err(LevelError::Json(_))));
+  }
+
+  #[test]
+  fn test_from_ascii() {
+    let state: State = "
+      policy: fix 3
+      x-----x
+      |X+  ·|
+      |·+ 2 |
+      |  ++X|
+      x-----x
+    "
+    .parse()
+    .unwrap();
+    assert_eq!((state.width(), state.height()), (5, 3));
+    assert_eq!(state.empty_policy(), &EmptyPolicy::Fix(3));
+    assert_eq!(state.snake_ends(), [BoardVec::new(0, 0), BoardVec::new(4, 2)]);
+    assert_eq!(state.field(BoardVec::new(4, 0)), Field::Empty);
+    assert_eq!(state.field(BoardVec::new(1, 1)), Field::Snake);
+    assert_eq!(state.unknowns(), 7);
+    assert_eq!(format!("policy: fix 3\n{state:?}").parse::<State>().unwrap(), state);
+
+    let level = example_level();
+    let (solution, puzzle) = level.states().unwrap();
+    let rows = format!("policy: ascending\n{}", level.level.join("\n"));
+    assert_eq!(State::from_ascii(&rows).unwrap(), solution);
+    assert_eq!(
+      State::from_ascii(&format!("policy: ascending\n{puzzle:?}")).unwrap(),
+      puzzle
+    );
+
+    assert!(matches!(
+      State::from_ascii("X.+\nX"),
+      Err(LevelError::WrongLineLength { line: 1, .. })
+    ));
+    assert!(matches!(
+      State::from_ascii("X.o.X"),
+      Err(LevelError::UnknownFieldChar { c: 'o', .. })
+    ));
+    assert!(matches!(
+      State::from_ascii("policy: fix\nX...X"),
+      Err(LevelError::InvalidPolicy(_))
+    ));
+    for policy in ["none", "fix 3", "range 2 5", "sizes 1,2,3", "distinct", "odd", "even"] {
+      assert_eq!(policy.parse::<SerializableEmptyPolicy>().unwrap().to_string(), policy);
     }
   }
 }
@@ -0,0 +1,344 @@
+use std::fmt;
+
+use crate::board::{Board, BoardVec};
+use crate::hint::{assumption_hint, next_hint, obvious_hint, Hint};
+use crate::serialize::{LevelData, LevelError};
+use crate::{Field, SnakeConnectedness, State, Violation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+  Snake,
+  Empty,
+  Clear,
+}
+
+impl Mark {
+  fn field(self) -> Field {
+    match self {
+      Mark::Snake => Field::Snake,
+      Mark::Empty => Field::Empty,
+      Mark::Clear => Field::Unknown,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkError {
+  OutOfBounds(BoardVec),
+  // clues and snake ends are given by the level
+  Clue(BoardVec),
+}
+
+impl fmt::Display for MarkError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MarkError::OutOfBounds(pos) => write!(f, "{pos:?} is outside the board"),
+      MarkError::Clue(pos) => write!(f, "{pos:?} is a clue and cannot be changed"),
+    }
+  }
+}
+
+impl std::error::Error for MarkError {}
+
+// Why a mark is flagged. A mark can break the rules together with earlier marks, or just differ from the solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+  Rule(Violation),
+  Solution,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Change {
+  pos: BoardVec,
+  before: Field,
+  after: Field,
+  // place of `pos` in the mark order before the change
+  index: Option<usize>,
+}
+
+// A player's attempt at a level. Marks are not checked when they are placed, the player may make mistakes and
+// `flags` points them out on request.
+#[derive(Clone)]
+pub struct GameSession {
+  puzzle: State,
+  solution: State,
+  max_assume_depth: usize,
+  // the solve path stored with the level, in solve order, empty when the level brings none
+  path: Vec<BoardVec>,
+  marks: Board<Field>,
+  // marked positions in the order they were marked, rule violations are blamed on the later mark
+  order: Vec<BoardVec>,
+  undo: Vec<Change>,
+  redo: Vec<Change>,
+}
+
+impl GameSession {
+  pub fn new(puzzle: State, solution: State, max_assume_depth: usize) -> Self {
+    let marks = Board::new(puzzle.width(), puzzle.height(), Field::Unknown);
+    Self {
+      puzzle,
+      solution,
+      max_assume_depth,
+      path: Vec::new(),
+      marks,
+      order: Vec::new(),
+      undo: Vec::new(),
+      redo: Vec::new(),
+    }
+  }
+
+  pub fn from_level(level: &LevelData) -> Result<Self, LevelError> {
+    let (solution, puzzle) = level.states()?;
+    let mut session = Self::new(puzzle, solution, level.max_assumption_depth());
+    session.path = level.moves();
+    Ok(session)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.puzzle.width()
+  }
+
+  pub fn height(&self) -> u32 {
+    self.puzzle.height()
+  }
+
+  pub fn puzzle(&self) -> &State {
+    &self.puzzle
+  }
+
+  pub fn contains(&self, pos: BoardVec) -> bool {
+    self.marks.get(pos).is_some()
+  }
+
+  pub fn is_clue(&self, pos: BoardVec) -> bool {
+    self.puzzle.board.get(pos).is_some_and(|&field| field != Field::Unknown)
+  }
+
+  // The field as the player sees it: the clue, or the player's mark
+  pub fn field(&self, pos: BoardVec) -> Field {
+    match self.puzzle.field(pos) {
+      Field::Unknown => self.marks[pos],
+      clue => clue,
+    }
+  }
+
+  // Returns whether the board changed. Marks that change nothing are not added to the history.
+  pub fn mark(&mut self, pos: BoardVec, mark: Mark) -> Result<bool, MarkError> {
+    if !self.contains(pos) {
+      return Err(MarkError::OutOfBounds(pos));
+    }
+    if self.is_clue(pos) {
+      return Err(MarkError::Clue(pos));
+    }
+
+    let change = Change {
+      pos,
+      before: self.marks[pos],
+      after: mark.field(),
+      index: self.order.iter().position(|&p| p == pos),
+    };
+    if change.before == change.after {
+      return Ok(false);
+    }
+
+    self.apply(change);
+    self.undo.push(change);
+    self.redo.clear();
+    Ok(true)
+  }
+
+  // Returns the position of the undone mark
+  pub fn undo(&mut self) -> Option<BoardVec> {
+    let change = self.undo.pop()?;
+    self.order.retain(|&p| p != change.pos);
+    if let Some(index) = change.index {
+      self.order.insert(index, change.pos);
+    }
+    self.marks[change.pos] = change.before;
+    self.redo.push(change);
+    Some(change.pos)
+  }
+
+  pub fn redo(&mut self) -> Option<BoardVec> {
+    let change = self.redo.pop()?;
+    self.apply(change);
+    self.undo.push(change);
+    Some(change.pos)
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo.is_empty()
+  }
+
+  fn apply(&mut self, change: Change) {
+    self.order.retain(|&p| p != change.pos);
+    if change.after != Field::Unknown {
+      self.order.push(change.pos);
+    }
+    self.marks[change.pos] = change.after;
+  }
+
+  // The puzzle with every mark that keeps to the rules, and the marks that do not
+  fn replay(&self) -> (State, Vec<(BoardVec, Violation)>) {
+    let mut state = self.puzzle.clone();
+    let mut broken = Vec::new();
+    for &pos in self.order.iter() {
+      if let Err(violation) = state.try_set(pos, self.marks[pos]) {
+        broken.push((pos, violation));
+      }
+    }
+    (state, broken)
+  }
+
+  pub fn flags(&self) -> Vec<(BoardVec, Flag)> {
+    let (_, broken) = self.replay();
+    let mut flags: Vec<(BoardVec, Flag)> = broken
+      .into_iter()
+      .map(|(pos, violation)| (pos, Flag::Rule(violation)))
+      .collect();
+    for &pos in self.order.iter() {
+      if self.marks[pos] != self.solution.field(pos) && !flags.iter().any(|&(p, _)| p == pos) {
+        flags.push((pos, Flag::Solution));
+      }
+    }
+    flags
+  }
+
+  // Solved once the marks keep to the rules, the snake connects both ends and every unmarked field can be empty.
+  // Levels have a unique solution, so this does not need the empty fields to be marked.
+  pub fn is_solved(&self) -> bool {
+    let (mut state, broken) = self.replay();
+    if !broken.is_empty() || state.is_snake_connected() != SnakeConnectedness::Connected {
+      return false;
+    }
+
+    let unknowns: Vec<BoardVec> = state
+      .board
+      .positions()
+      .filter(|&pos| state.field(pos) == Field::Unknown)
+      .collect();
+    unknowns.into_iter().all(|pos| state.try_set(pos, Field::Empty).is_ok())
+      && state.empty_policy().is_still_possible(0)
+  }
+
+  // The first field of the stored solve path that is still open, ignoring marks that differ from the solution.
+  // Without a stored path, or when the marks lead somewhere the path cannot be explained from, this is the simplest
+  // deduction instead.
+  pub fn hint(&self) -> Option<Hint> {
+    let mut state = self.puzzle.clone();
+    for &pos in self.order.iter() {
+      if self.marks[pos] == self.solution.field(pos) {
+        state.set(pos, self.marks[pos]);
+      }
+    }
+    let next = self.path.iter().find(|&&pos| state.field(pos) == Field::Unknown);
+    next
+      .and_then(|&pos| self.explain(&state, pos))
+      .or_else(|| next_hint(&state, self.max_assume_depth))
+  }
+
+  fn explain(&self, state: &State, pos: BoardVec) -> Option<Hint> {
+    let field = self.solution.field(pos);
+    obvious_hint(state, pos)
+      .into_iter()
+      .chain((1..=self.max_assume_depth).filter_map(|depth| assumption_hint(state, pos, depth)))
+      .find(|hint| hint.field == field)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Flag, GameSession, Mark, MarkError};
+  use crate::board::BoardVec;
+  use crate::test_util::example_level;
+  use crate::Field;
+
+  fn session() -> GameSession {
+    let data = example_level();
+    GameSession::from_level(&data).unwrap()
+  }
+
+  #[test]
+  fn test_undo_redo() {
+    let mut session = session();
+    let pos = session
+      .puzzle()
+      .board
+      .positions()
+      .find(|&p| !session.is_clue(p))
+      .unwrap();
+    let clue = session
+      .puzzle()
+      .board
+      .positions()
+      .find(|&p| session.is_clue(p))
+      .unwrap();
+    assert_eq!(session.mark(clue, Mark::Empty), Err(MarkError::Clue(clue)));
+    assert_eq!(
+      session.mark(BoardVec::new(7, 0), Mark::Empty),
+      Err(MarkError::OutOfBounds(BoardVec::new(7, 0)))
+    );
+
+    assert_eq!(session.mark(pos, Mark::Snake), Ok(true));
+    assert_eq!(session.mark(pos, Mark::Snake), Ok(false));
+    assert_eq!(session.mark(pos, Mark::Empty), Ok(true));
+    assert_eq!(session.field(pos), Field::Empty);
+    assert_eq!(session.undo(), Some(pos));
+    assert_eq!(session.field(pos), Field::Snake);
+    assert_eq!(session.undo(), Some(pos));
+    assert_eq!(session.field(pos), Field::Unknown);
+    assert_eq!(session.undo(), None);
+    assert_eq!(session.redo(), Some(pos));
+    assert_eq!(session.field(pos), Field::Snake);
+
+    session.mark(pos, Mark::Clear).unwrap();
+    assert!(!session.can_redo());
+    assert_eq!(session.field(pos), Field::Unknown);
+  }
+
+  #[test]
+  fn test_flags_and_completion() {
+    let mut session = session();
+    let wrong = session
+      .puzzle()
+      .board
+      .positions()
+      .find(|&p| !session.is_clue(p) && session.solution.field(p) == Field::Empty)
+      .unwrap();
+    session.mark(wrong, Mark::Snake).unwrap();
+    assert!(session.flags().iter().any(|&(pos, _)| pos == wrong));
+    session.mark(wrong, Mark::Clear).unwrap();
+
+    assert!(!session.is_solved());
+    while let Some(hint) = session.hint() {
+      let mark = if hint.field.is_snake() {
+        Mark::Snake
+      } else {
+        Mark::Empty
+      };
+      session.mark(hint.pos, mark).unwrap();
+      assert_eq!(session.flags(), Vec::<(BoardVec, Flag)>::new());
+    }
+    assert!(session.is_solved());
+  }
+
+  #[test]
+  fn test_hints_follow_stored_path() {
+    let data = example_level();
+    let mut session = GameSession::from_level(&data).unwrap();
+    for pos in data.moves() {
+      let hint = session.hint().unwrap();
+      assert_eq!(hint.pos, pos);
+      let mark = if hint.field.is_snake() {
+        Mark::Snake
+      } else {
+        Mark::Empty
+      };
+      session.mark(pos, mark).unwrap();
+    }
+  }
+}
@@ -0,0 +1,464 @@
+use std::fmt;
+
+use crate::board::BoardVec;
+use crate::budget::{Budget, GaveUp};
+use crate::generate::GeneratedLevel;
+use crate::serialize::{LevelData, LevelError, SerializableEmptyPolicy};
+use crate::solver::Symmetry;
+use crate::{solve_within, Field, State};
+
+// Share codes are the bits below, most significant first, with a CRC-16 of them appended and encoded as base64url
+// without padding:
+//
+// version (4), width - 1 (6), height - 1 (6), max assumption depth (4), policy tag (4) and its numbers (12 each,
+// `sizes` starts with their count), the board indices of both snake ends (12 each), one bit per field for clue and
+// then one bit per clue besides the snake ends for snake. The clue symmetry follows (3, 0 for none), a seed bit and
+// the seed (64) if it is set, and the length of the author (8) and its UTF-8 bytes.
+//
+// Version 1 and 2 codes had one bit per field for snake in the solution in front of the clue bits, version 1 codes
+// end after the clue bits.
+//
+// The shipped 8x8 levels take 38 characters each. The solution is the one the clues allow, moves and difficulty are
+// derived again when decoding. The moves follow the canonical trace (see `canonical_trace`) like those of generated
+// and rated levels, so the difficulty comes out the same as well.
+pub const SHARE_CODE_VERSION: u32 = 3;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug)]
+pub enum ShareCodeError {
+  InvalidCharacter(char),
+  // the code ends before all fields are read
+  Truncated,
+  ChecksumMismatch,
+  UnsupportedVersion(u32),
+  InvalidSymmetry(usize),
+  // the author is not valid UTF-8
+  InvalidAuthor,
+  // boards larger than 64x64, numbers beyond 4095, assumption depths beyond 15 or authors longer than 255 bytes do
+  // not fit into a code
+  TooLarge,
+  // the decoded clues do not lead to the decoded solution
+  NotDeducible,
+  // the decoded clues also allow another solution
+  MultipleSolutions,
+  Level(LevelError),
+}
+
+impl fmt::Display for ShareCodeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShareCodeError::InvalidCharacter(c) => write!(f, "invalid character '{c}' in share code"),
+      ShareCodeError::Truncated => write!(f, "share code is too short"),
+      ShareCodeError::ChecksumMismatch => {
+        write!(f, "share code checksum does not match, it was probably mistyped")
+      }
+      ShareCodeError::UnsupportedVersion(version) => write!(
+        f,
+        "unsupported share code version {version}, at most {SHARE_CODE_VERSION} is supported"
+      ),
+      ShareCodeError::InvalidSymmetry(symmetry) => write!(f, "invalid symmetry {symmetry} in share code"),
+      ShareCodeError::InvalidAuthor => write!(f, "the author in the share code is not valid UTF-8"),
+      ShareCodeError::TooLarge => write!(f, "level is too large for a share code"),
+      ShareCodeError::NotDeducible => write!(f, "the clues of the share code do not lead to its solution"),
+      ShareCodeError::MultipleSolutions => write!(f, "the clues of the share code allow more than one solution"),
+      ShareCodeError::Level(err) => write!(f, "{err}"),
+    }
+  }
+}
+
+impl std::error::Error for ShareCodeError {}
+
+impl From<LevelError> for ShareCodeError {
+  fn from(err: LevelError) -> Self {
+    ShareCodeError::Level(err)
+  }
+}
+
+#[derive(Default)]
+struct BitWriter {
+  bytes: Vec<u8>,
+  len: usize,
+}
+
+impl BitWriter {
+  fn push(&mut self, value: usize, bits: u32) -> Result<(), ShareCodeError> {
+    if value >> bits != 0 {
+      return Err(ShareCodeError::TooLarge);
+    }
+    for i in (0..bits).rev() {
+      if self.len.is_multiple_of(8) {
+        self.bytes.push(0);
+      }
+      if value >> i & 1 == 1 {
+        *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+      }
+      self.len += 1;
+    }
+    Ok(())
+  }
+}
+
+struct BitReader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl BitReader<'_> {
+  fn read(&mut self, bits: u32) -> Result<usize, ShareCodeError> {
+    let mut value = 0;
+    for _ in 0..bits {
+      let byte = self.bytes.get(self.pos / 8).ok_or(ShareCodeError::Truncated)?;
+      value = value << 1 | (byte >> (7 - self.pos % 8) & 1) as usize;
+      self.pos += 1;
+    }
+    Ok(value)
+  }
+}
+
+// CRC-16/CCITT-FALSE
+fn checksum(bytes: &[u8]) -> u16 {
+  let mut crc: u16 = 0xffff;
+  for &byte in bytes {
+    crc ^= (byte as u16) << 8;
+    for _ in 0..8 {
+      crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+    }
+  }
+  crc
+}
+
+fn to_base64url(bytes: &[u8]) -> String {
+  let mut out = String::new();
+  for chunk in bytes.chunks(3) {
+    let n = chunk
+      .iter()
+      .enumerate()
+      .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+    for i in 0..=chunk.len() {
+      out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+    }
+  }
+  out
+}
+
+fn from_base64url(code: &str) -> Result<Vec<u8>, ShareCodeError> {
+  let mut bytes = Vec::new();
+  let (mut n, mut bits) = (0u32, 0);
+  for c in code.chars() {
+    let value = ALPHABET
+      .iter()
+      .position(|&a| a as char == c)
+      .ok_or(ShareCodeError::InvalidCharacter(c))?;
+    n = n << 6 | value as u32;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      bytes.push((n >> bits) as u8);
+      n &= (1 << bits) - 1;
+    }
+  }
+  Ok(bytes)
+}
+
+fn policy_numbers(policy: &SerializableEmptyPolicy) -> (usize, Vec<usize>) {
+  match policy {
+    SerializableEmptyPolicy::None => (0, vec![]),
+    &SerializableEmptyPolicy::Fix { fix_size } => (1, vec![fix_size]),
+    SerializableEmptyPolicy::Ascending { .. } => (2, vec![]),
+    &SerializableEmptyPolicy::Range { min, max } => (3, vec![min, max]),
+    SerializableEmptyPolicy::Sizes { sizes } => (4, [vec![sizes.len()], sizes.clone()].concat()),
+    SerializableEmptyPolicy::Distinct => (5, vec![]),
+    SerializableEmptyPolicy::Odd => (6, vec![]),
+    SerializableEmptyPolicy::Even => (7, vec![]),
+  }
+}
+
+fn read_policy(reader: &mut BitReader) -> Result<SerializableEmptyPolicy, ShareCodeError> {
+  Ok(match reader.read(4)? {
+    0 => SerializableEmptyPolicy::None,
+    1 => SerializableEmptyPolicy::Fix {
+      fix_size: reader.read(12)?,
+    },
+    2 => SerializableEmptyPolicy::Ascending { top: 0 },
+    3 => SerializableEmptyPolicy::Range {
+      min: reader.read(12)?,
+      max: reader.read(12)?,
+    },
+    4 => {
+      let count = reader.read(12)?;
+      let sizes = (0..count).map(|_| reader.read(12)).collect::<Result<_, _>>()?;
+      SerializableEmptyPolicy::Sizes { sizes }
+    }
+    5 => SerializableEmptyPolicy::Distinct,
+    6 => SerializableEmptyPolicy::Odd,
+    7 => SerializableEmptyPolicy::Even,
+    _ => return Err(ShareCodeError::Level(LevelError::UnsupportedEmptyPolicy)),
+  })
+}
+
+// symmetry, seed and author, see the layout at the top
+fn read_extras(reader: &mut BitReader) -> Result<(Option<Symmetry>, Option<u64>, String), ShareCodeError> {
+  let symmetry = match reader.read(3)? {
+    0 => None,
+    i => Some(*Symmetry::ALL.get(i - 1).ok_or(ShareCodeError::InvalidSymmetry(i))?),
+  };
+  let seed = match reader.read(1)? {
+    0 => None,
+    _ => Some((reader.read(32)? as u64) << 32 | reader.read(32)? as u64),
+  };
+  let len = reader.read(8)?;
+  let author = (0..len)
+    .map(|_| reader.read(8).map(|b| b as u8))
+    .collect::<Result<_, _>>()?;
+  let author = String::from_utf8(author).map_err(|_| ShareCodeError::InvalidAuthor)?;
+  Ok((symmetry, seed, author))
+}
+
+impl LevelData {
+  pub fn to_share_code(&self) -> Result<String, ShareCodeError> {
+    let solution = self.solution()?;
+    let puzzle = self.puzzle()?;
+    let (width, height) = (self.width() as usize, self.height() as usize);
+    let index = |pos: BoardVec| pos.x as usize + pos.y as usize * width;
+
+    let mut writer = BitWriter::default();
+    writer.push(SHARE_CODE_VERSION as usize, 4)?;
+    writer.push(width.wrapping_sub(1), 6)?;
+    writer.push(height.wrapping_sub(1), 6)?;
+    writer.push(self.max_assumption_depth(), 4)?;
+    let (tag, numbers) = policy_numbers(&SerializableEmptyPolicy::new(&self.empty_policy()?));
+    writer.push(tag, 4)?;
+    for number in numbers {
+      writer.push(number, 12)?;
+    }
+    for &end in solution.snake_ends() {
+      writer.push(index(end), 12)?;
+    }
+    for pos in puzzle.board.positions() {
+      writer.push((puzzle.field(pos) != Field::Unknown) as usize, 1)?;
+    }
+    for pos in puzzle.board.positions() {
+      if !matches!(puzzle.field(pos), Field::Unknown | Field::SnakeEnd) {
+        writer.push(puzzle.field(pos).is_snake() as usize, 1)?;
+      }
+    }
+    let symmetry = self
+      .symmetry()
+      .map_or(0, |s| Symmetry::ALL.iter().position(|&a| a == s).unwrap() + 1);
+    writer.push(symmetry, 3)?;
+    writer.push(self.seed().is_some() as usize, 1)?;
+    if let Some(seed) = self.seed() {
+      writer.push((seed >> 32) as usize, 32)?;
+      writer.push(seed as u32 as usize, 32)?;
+    }
+    writer.push(self.author().len(), 8)?;
+    for &byte in self.author().as_bytes() {
+      writer.push(byte as usize, 8)?;
+    }
+
+    let mut bytes = writer.bytes;
+    bytes.extend(checksum(&bytes).to_be_bytes());
+    Ok(to_base64url(&bytes))
+  }
+
+  pub fn from_share_code(code: &str) -> Result<Self, ShareCodeError> {
+    Self::from_share_code_within(code, &Budget::unlimited()).expect("an unlimited budget never runs out")
+  }
+
+  // Like `from_share_code`, a code is only decoded if the budget lasts for checking that its solution is unique.
+  pub fn from_share_code_within(code: &str, budget: &Budget) -> Result<Result<Self, ShareCodeError>, GaveUp> {
+    let level = Self::from_share_code2(code, budget);
+    budget.check(level)
+  }
+
+  // Any error once the budget is used up, `from_share_code_within` reports that as `GaveUp` instead.
+  fn from_share_code2(code: &str, budget: &Budget) -> Result<Self, ShareCodeError> {
+    let bytes = from_base64url(code.trim())?;
+    if bytes.len() < 3 {
+      return Err(ShareCodeError::Truncated);
+    }
+    let (payload, crc) = bytes.split_at(bytes.len() - 2);
+    if checksum(payload).to_be_bytes() != crc {
+      return Err(ShareCodeError::ChecksumMismatch);
+    }
+
+    let mut reader = BitReader { bytes: payload, pos: 0 };
+    let version = reader.read(4)? as u32;
+    if !(1..=SHARE_CODE_VERSION).contains(&version) {
+      return Err(ShareCodeError::UnsupportedVersion(version));
+    }
+    let width = reader.read(6)? + 1;
+    let height = reader.read(6)? + 1;
+    let max_assume_depth = reader.read(4)?;
+    let policy = read_policy(&mut reader)?;
+    let pos = |i: usize| BoardVec::new((i % width) as i32, (i / width) as i32);
+    let ends = [pos(reader.read(12)?), pos(reader.read(12)?)];
+    let mut read_bits = |count: usize| {
+      (0..count)
+        .map(|_| reader.read(1).map(|bit| bit == 1))
+        .collect::<Result<Vec<bool>, _>>()
+    };
+    let snake = if version < 3 {
+      Some(read_bits(width * height)?)
+    } else {
+      None
+    };
+    let clues = read_bits(width * height)?;
+    let is_clue = |i: usize| clues[i] && !ends.contains(&pos(i));
+    let clue_fields: Vec<BoardVec> = (0..width * height).filter(|&i| is_clue(i)).map(pos).collect();
+    let clue_snake = match snake {
+      Some(snake) => (0..width * height).filter(|&i| is_clue(i)).map(|i| snake[i]).collect(),
+      None => read_bits(clue_fields.len())?,
+    };
+    let extras = if version >= 2 {
+      Some(read_extras(&mut reader)?)
+    } else {
+      None
+    };
+
+    if let Some(&end) = ends.iter().find(|end| end.y as usize >= height) {
+      return Err(LevelError::OutOfBounds(end).into());
+    }
+    if ends[0].dist(ends[1]) < 2 {
+      return Err(LevelError::SnakeEndsTooClose(ends[0], ends[1]).into());
+    }
+    let initial = State::new(
+      width as u32,
+      height as u32,
+      ends[0],
+      ends[1],
+      policy.to_empty_policy(width as u32, height as u32)?,
+    );
+    // deductions that merely complete a solution do not rule out a second one
+    let mut puzzle = initial.clone();
+    let mut initial_open = Vec::new();
+    for (&pos, snake) in clue_fields.iter().zip(clue_snake) {
+      if initial.field(pos) == Field::Unknown {
+        let field = if snake { Field::Snake } else { Field::Empty };
+        puzzle.try_set(pos, field).map_err(|_| ShareCodeError::NotDeducible)?;
+        initial_open.push(pos);
+      }
+    }
+    let mut results = Vec::new();
+    solve_within(puzzle, &mut results, 2, budget).map_err(|_| ShareCodeError::NotDeducible)?;
+    let solution = match &results[..] {
+      [] => return Err(ShareCodeError::NotDeducible),
+      [solution] => solution,
+      _ => return Err(ShareCodeError::MultipleSolutions),
+    };
+
+    let level = GeneratedLevel::from_clues_within(&initial, solution, initial_open, max_assume_depth, budget)
+      .ok()
+      .flatten()
+      .ok_or(ShareCodeError::NotDeducible)?
+      .level_data();
+    level.solution()?;
+    Ok(match extras {
+      Some((symmetry, seed, author)) => level.with_symmetry(symmetry).with_seed(seed).with_author(author),
+      None => level,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::{from_base64url, to_base64url, ShareCodeError};
+  use crate::board::BoardVec;
+  use crate::budget::{Budget, GaveUp};
+  use crate::generate::GeneratedLevel;
+  use crate::serialize::LevelData;
+  use crate::test_util::example_level;
+  use crate::verify::verify_level;
+  use crate::{EmptyPolicy, State};
+
+  #[test]
+  fn test_base64url() {
+    for len in 0..8 {
+      let bytes: Vec<u8> = (0..len).map(|i| (i * 97 + 13) as u8).collect();
+      assert_eq!(from_base64url(&to_base64url(&bytes)).unwrap(), bytes);
+    }
+    assert_eq!(to_base64url(b"\xfb\xff"), "-_8");
+  }
+
+  #[test]
+  fn test_share_code() {
+    let level = example_level();
+    let code = level.to_share_code().unwrap();
+    assert!(code.len() < 40, "{code}");
+
+    let decoded = LevelData::from_share_code(&code).unwrap();
+    assert_eq!(decoded.solution().unwrap(), level.solution().unwrap());
+    assert_eq!(decoded.puzzle().unwrap(), level.puzzle().unwrap());
+    assert_eq!(decoded.max_assumption_depth(), level.max_assumption_depth());
+    assert_eq!(decoded.to_share_code().unwrap(), code);
+
+    // a version 2 code, which still carries the solution
+    let old = LevelData::from_share_code("IYYSAjAw-RuRsb1OgEABBQkIQCVRvYmlhcyBLLj2QA").unwrap();
+    assert_eq!(old.puzzle().unwrap(), level.puzzle().unwrap());
+    assert_eq!(old.solution().unwrap(), level.solution().unwrap());
+    assert_eq!(old.author(), level.author());
+
+    let level = level.with_seed(Some(u64::MAX - 1)).with_author("Zoë".to_string());
+    let decoded = LevelData::from_share_code(&level.to_share_code().unwrap()).unwrap();
+    assert_eq!(decoded.seed(), Some(u64::MAX - 1));
+    assert_eq!(decoded.author(), "Zoë");
+
+    let mut typo: Vec<char> = code.chars().collect();
+    typo[5] = if typo[5] == 'A' { 'B' } else { 'A' };
+    let typo: String = typo.into_iter().collect();
+    assert!(matches!(
+      LevelData::from_share_code(&typo),
+      Err(ShareCodeError::ChecksumMismatch)
+    ));
+    assert!(matches!(
+      LevelData::from_share_code("abc$"),
+      Err(ShareCodeError::InvalidCharacter('$'))
+    ));
+  }
+
+  #[test]
+  fn test_shipped_levels() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/levels");
+    for entry in fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.extension().is_some_and(|ext| ext == "json") {
+        let level = LevelData::load(&path).unwrap();
+        let decoded = LevelData::from_share_code(&level.to_share_code().unwrap()).unwrap();
+        // the moves may come in another order, replaying them has to lead to the same solution
+        assert_eq!(decoded.solution().unwrap(), level.solution().unwrap());
+        verify_level(&decoded).unwrap();
+        assert_eq!(decoded.moves().len(), level.moves().len());
+        assert_eq!(decoded.difficulty(), level.difficulty(), "{}", path.display());
+        assert_eq!(decoded.author(), level.author());
+      }
+    }
+  }
+
+  #[test]
+  fn test_share_code_unique() {
+    // the snake can also run down the middle column, but assuming this row completes the puzzle
+    let solution: State = "
+      |X..|
+      |+++|
+      |..X|
+    "
+    .parse()
+    .unwrap();
+    let initial = State::new(3, 3, BoardVec::new(0, 0), BoardVec::new(2, 2), EmptyPolicy::None);
+    let level = GeneratedLevel::from_clues(&initial, &solution, Vec::new(), 2).unwrap();
+    let code = level.level_data().to_share_code().unwrap();
+
+    assert!(matches!(
+      LevelData::from_share_code(&code),
+      Err(ShareCodeError::MultipleSolutions)
+    ));
+    let budget = Budget::unlimited().with_max_nodes(1);
+    assert!(matches!(
+      LevelData::from_share_code_within(&code, &budget),
+      Err(GaveUp::NodeLimit)
+    ));
+  }
+}
@@ -1,13 +1,18 @@
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{BinaryHeap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::mem;
+use std::str::FromStr;
+use std::time::Instant;
+use std::{fmt, mem};
 
 #[allow(unused_imports)]
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 
 use crate::board::BoardVec;
+use crate::budget::{Budget, GaveUp};
 use crate::list::List;
+use crate::progress::SearchProgress;
 use crate::{Field, SnakeConnectedness, State, Throwaway};
 
 #[derive(Debug, Clone)]
@@ -76,7 +81,21 @@ pub fn fill_obvious(mut state: State, moves: &mut impl Extend<BoardVec>) -> Fill
 }
 
 pub fn solve(state: State, results: &mut Vec<State>, max_results: usize) {
-  if results.len() >= max_results {
+  solve2(state, results, max_results, &Budget::unlimited());
+}
+
+pub fn solve_within(
+  state: State,
+  results: &mut Vec<State>,
+  max_results: usize,
+  budget: &Budget,
+) -> Result<(), GaveUp> {
+  solve2(state, results, max_results, budget);
+  budget.check(())
+}
+
+fn solve2(state: State, results: &mut Vec<State>, max_results: usize, budget: &Budget) {
+  if results.len() >= max_results || !budget.spend() {
     return;
   }
 
@@ -94,22 +113,171 @@ pub fn solve(state: State, results: &mut Vec<State>, max_results: usize) {
     {
       let mut s = state.clone();
       s.set(pos, Field::Snake);
-      solve(s, results, max_results);
+      solve2(s, results, max_results, budget);
     }
 
     {
       let mut s = state;
       s.set(pos, Field::Empty);
-      solve(s, results, max_results)
+      solve2(s, results, max_results, budget)
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolutionCount {
+  // at most the cap given to `count_solutions`
+  pub solutions: usize,
+  // false if the search stopped at the cap, `solutions` is only a lower bound then
+  pub exhausted: bool,
+  // number of states visited
+  pub nodes: usize,
+  // deepest recursion, i.e. the most fields guessed at once
+  pub max_depth: usize,
+}
+
+impl SolutionCount {
+  pub fn is_unique(&self) -> bool {
+    self.solutions == 1 && self.exhausted
+  }
+}
+
+// Like `solve`, but only counts the solutions.
+pub fn count_solutions(state: State, cap: usize) -> SolutionCount {
+  count_solutions_within(state, cap, &Budget::unlimited()).expect("an unlimited budget never runs out")
+}
+
+pub fn count_solutions_within(state: State, cap: usize, budget: &Budget) -> Result<SolutionCount, GaveUp> {
+  let mut count = SolutionCount {
+    exhausted: true,
+    ..SolutionCount::default()
+  };
+  count_solutions2(state, cap, 0, &mut count, budget);
+  budget.check(count)
+}
+
+fn count_solutions2(state: State, cap: usize, depth: usize, count: &mut SolutionCount, budget: &Budget) {
+  if count.solutions >= cap {
+    count.exhausted = false;
+    return;
+  }
+  if !budget.spend() {
+    return;
+  }
+
+  count.nodes += 1;
+  count.max_depth = count.max_depth.max(depth);
+  let state = match fill_obvious(state, &mut Throwaway) {
+    FillResult::Contradiction => return,
+    FillResult::Solved(_) => {
+      count.solutions += 1;
+      return;
+    }
+    FillResult::Ok(state, _) => state,
+  };
+
+  if let Some(pos) = state.board.positions().find(|&p| state.field(p) == Field::Unknown) {
+    let mut s = state.clone();
+    s.set(pos, Field::Snake);
+    count_solutions2(s, cap, depth + 1, count, budget);
+
+    let mut s = state;
+    s.set(pos, Field::Empty);
+    count_solutions2(s, cap, depth + 1, count, budget);
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Deduction {
+  Obvious,
+  Assumption(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SolveStep {
+  pub pos: BoardVec,
+  pub field: Field,
+  pub deduction: Deduction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Symmetry {
+  // rotation by 180 degrees around the centre
+  Rotational,
+  // the left half mirrors the right half
+  Horizontal,
+  // the top half mirrors the bottom half
+  Vertical,
+  // mirrored at the diagonal from the top left corner, only possible on square boards
+  Diagonal,
+}
+
+impl Symmetry {
+  pub const ALL: [Symmetry; 4] = [
+    Symmetry::Rotational,
+    Symmetry::Horizontal,
+    Symmetry::Vertical,
+    Symmetry::Diagonal,
+  ];
+
+  pub fn fits(self, width: u32, height: u32) -> bool {
+    self != Symmetry::Diagonal || width == height
+  }
+
+  pub fn image(self, pos: BoardVec, width: u32, height: u32) -> BoardVec {
+    let (w, h) = (width as i32, height as i32);
+    match self {
+      Symmetry::Rotational => BoardVec::new(w - 1 - pos.x, h - 1 - pos.y),
+      Symmetry::Horizontal => BoardVec::new(w - 1 - pos.x, pos.y),
+      Symmetry::Vertical => BoardVec::new(pos.x, h - 1 - pos.y),
+      Symmetry::Diagonal => BoardVec::new(pos.y, pos.x),
+    }
+  }
+
+  // `pos` and its image, which is the same field for positions on the axis
+  pub fn orbit(self, pos: BoardVec, width: u32, height: u32) -> Vec<BoardVec> {
+    let image = self.image(pos, width, height);
+    if image == pos {
+      vec![pos]
+    } else {
+      vec![pos, image]
     }
   }
 }
 
+impl fmt::Display for Symmetry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Symmetry::Rotational => write!(f, "rotational"),
+      Symmetry::Horizontal => write!(f, "horizontal"),
+      Symmetry::Vertical => write!(f, "vertical"),
+      Symmetry::Diagonal => write!(f, "diagonal"),
+    }
+  }
+}
+
+impl FromStr for Symmetry {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Symmetry::ALL
+      .into_iter()
+      .find(|symmetry| symmetry.to_string().eq_ignore_ascii_case(s))
+      .ok_or_else(|| format!("unknown symmetry `{s}`"))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SolutionPath {
+  pub initial_open: Vec<BoardVec>,
+  pub steps: Vec<SolveStep>,
+}
+
 #[derive(Debug, Clone)]
 struct Item {
   initial_open_count: usize,
   state: State,
-  moves: List<BoardVec>,
+  moves: List<SolveStep>,
   initial_open: List<BoardVec>,
   finished: bool,
 }
@@ -132,13 +300,13 @@ impl Item {
     self.initial_open_count.hash(hasher);
     for m in self.moves.iter() {
       let move_hasher = &mut DefaultHasher::new();
-      m.hash(move_hasher);
+      m.pos.hash(move_hasher);
       move_hasher.finish().hash(hasher);
     }
     hasher.finish()
   }
 
-  fn with_move(self, pos: BoardVec, field: Field) -> Self {
+  fn with_step(self, step: SolveStep) -> Self {
     let Item {
       initial_open_count,
       mut state,
@@ -147,8 +315,8 @@ impl Item {
       finished,
     } = self;
 
-    state.set(pos, field);
-    moves.push(pos);
+    state.set(step.pos, step.field);
+    moves.push(step);
 
     Self {
       initial_open_count,
@@ -168,7 +336,10 @@ impl Item {
       finished,
     } = self;
 
-    state.set(pos, solution.field(pos));
+    // with symmetric clues, a field may have been deduced before its mirror image is opened
+    if state.field(pos) == Field::Unknown {
+      state.set(pos, solution.field(pos));
+    }
     initial_open_count += 1;
     initial_open.push(pos);
 
@@ -190,17 +361,23 @@ impl Item {
       ..
     } = self;
 
-    let (state, finished) = match fill_obvious(state.clone(), &mut moves) {
+    let mut filled = Vec::new();
+    let (state, finished) = match fill_obvious(state.clone(), &mut filled) {
       FillResult::Contradiction => {
-        println!("Solution:\n{:?}", solution);
-        println!("State:\n{:?}", state);
-        fill_obvious(state, &mut Throwaway);
-        panic!("No item should ever be in contradiction!")
+        panic!("No item should ever be in contradiction!\nSolution:\n{solution:?}\nState:\n{state:?}")
       }
       FillResult::Solved(state) => (state, true),
       FillResult::Ok(state, _) => (state, false),
     };
 
+    for pos in filled {
+      moves.push(SolveStep {
+        pos,
+        field: state.field(pos),
+        deduction: Deduction::Obvious,
+      });
+    }
+
     Self {
       initial_open_count,
       state,
@@ -231,32 +408,86 @@ impl PartialOrd for Item {
   }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn find_solution_path(
   begin: State,
   solution: &State,
   max_assume_depth: usize,
-) -> (Vec<BoardVec>, Vec<BoardVec>) {
+  budget: &Budget,
+) -> Result<Option<(Vec<BoardVec>, Vec<BoardVec>)>, GaveUp> {
+  let path = find_solution_trace(begin, solution, max_assume_depth, None, budget)?;
+  Ok(path.map(|path| {
+    (
+      path.initial_open,
+      path.steps.iter().rev().map(|step| step.pos).collect(),
+    )
+  }))
+}
+
+// Fails if every way of opening clues, in symmetric pairs if `symmetry` is given, gets stuck before the puzzle
+// is solved.
+pub fn find_solution_trace(
+  begin: State,
+  solution: &State,
+  max_assume_depth: usize,
+  symmetry: Option<Symmetry>,
+  budget: &Budget,
+) -> Result<Option<SolutionPath>, GaveUp> {
+  if symmetry.is_some_and(|symmetry| !symmetry.fits(begin.width(), begin.height())) {
+    return Ok(None);
+  }
+
   let mut items = BinaryHeap::new();
-  items.push(Item::new(begin));
-  let item = find_solution_path2(solution, items, max_assume_depth);
-  (
-    item.initial_open.iter().cloned().collect(),
-    item.moves.iter().cloned().collect(),
-  )
+  items.push(Item::new(begin.clone()));
+  let item = find_solution_path2(solution, items, max_assume_depth, symmetry, budget);
+  let Some(item) = budget.check(item)? else {
+    return Ok(None);
+  };
+  let initial_open: Vec<BoardVec> = item.initial_open.iter().cloned().collect();
+
+  // The player sees all clues from the start, while the search opened them one after another. Later clues can
+  // make earlier steps easier, and fields deduced before their mirror image was opened became clues themselves, so
+  // the steps are those of the canonical trace of the finished puzzle rather than the ones the search made.
+  let mut puzzle = begin;
+  for &pos in initial_open.iter() {
+    puzzle.set(pos, solution.field(pos));
+  }
+  let steps = match budget.check(canonical_trace2(&puzzle, max_assume_depth, budget))? {
+    Ok((_, steps)) if steps.iter().all(|step| step.field == solution.field(step.pos)) => steps,
+    _ => return Ok(None),
+  };
+
+  Ok(Some(SolutionPath { initial_open, steps }))
 }
 
-fn find_solution_path2(solution: &State, mut items: BinaryHeap<Item>, max_depth: usize) -> Item {
+fn find_solution_path2(
+  solution: &State,
+  mut items: BinaryHeap<Item>,
+  max_depth: usize,
+  symmetry: Option<Symmetry>,
+  budget: &Budget,
+) -> Option<Item> {
+  let (width, height) = (solution.width(), solution.height());
   let mut fingerprints = HashSet::new();
+  let start = Instant::now();
   loop {
-    assert!(!items.is_empty());
-    println!("Items in queue {}", items.len());
+    if items.is_empty() || !budget.spend() {
+      return None;
+    }
+    let best = items.peek().unwrap();
+    budget.report(&SearchProgress {
+      queue: items.len(),
+      best_unknowns: best.state.unknowns,
+      clues: best.initial_open_count,
+      nodes: budget.nodes(),
+      elapsed: start.elapsed(),
+    });
     for item in mem::take(&mut items).drain_sorted().take(100) {
-      println!(
-        "Item(opened: {}, unknowns: {})",
-        item.initial_open_count, item.state.unknowns
-      );
+      if !budget.spend() {
+        return None;
+      }
       if item.finished {
-        return item;
+        return Some(item);
       }
 
       //let mut pushed_one = false;
@@ -264,8 +495,21 @@ fn find_solution_path2(solution: &State, mut items: BinaryHeap<Item>, max_depth:
         //print!("{:?}", pos);
         if item.state.field(pos) == Field::Unknown && solution.field(pos) == Field::Empty {
           //&& (!pushed_one || thread_rng().gen_range(0..=(items.len() / 200)) == 0) {
-          let new_item = item.clone().with_opened(pos, solution);
-          if let Ok(new_item) = further_item_multi(new_item, max_depth, solution) {
+          let orbit = match symmetry {
+            Some(symmetry) => symmetry.orbit(pos, width, height),
+            None => vec![pos],
+          };
+          if orbit.iter().any(|&p| solution.field(p) != Field::Empty) {
+            continue;
+          }
+          if !budget.spend() {
+            return None;
+          }
+
+          let new_item = orbit
+            .into_iter()
+            .fold(item.clone(), |new_item, p| new_item.with_opened(p, solution));
+          if let Ok(new_item) = further_item_multi(new_item, max_depth, solution, budget) {
             if fingerprints.insert(new_item.fingerprint()) {
               items.push(new_item);
               //pushed_one = true;
@@ -277,10 +521,11 @@ fn find_solution_path2(solution: &State, mut items: BinaryHeap<Item>, max_depth:
   }
 }
 
-fn further_item_multi(mut item: Item, max_depth: usize, solution: &State) -> Result<Item, Item> {
+#[allow(clippy::result_large_err)]
+fn further_item_multi(mut item: Item, max_depth: usize, solution: &State, budget: &Budget) -> Result<Item, Item> {
   let mut furthered = false;
   loop {
-    item = match further_item(item, max_depth, solution) {
+    item = match further_item(item, max_depth, solution, budget) {
       Ok(item) => item,
       Err(item) if furthered => return Ok(item),
       Err(item) => return Err(item),
@@ -290,44 +535,15 @@ fn further_item_multi(mut item: Item, max_depth: usize, solution: &State) -> Res
   }
 }
 
-fn further_item(item: Item, max_depth: usize, solution: &State) -> Result<Item, Item> {
+#[allow(clippy::result_large_err)]
+fn further_item(item: Item, max_depth: usize, solution: &State, budget: &Budget) -> Result<Item, Item> {
   let moves_before_fill = item.moves.clone();
   let item = item.with_filled(solution);
   if max_depth > 0 {
     let state = &item.state;
     for pos in state.board.positions() {
-      if state.field(pos) == Field::Unknown {
-        let res_snake = {
-          let mut s = state.clone();
-          s.set(pos, Field::Snake);
-          find_contradiction(s, max_depth, pos)
-        };
-
-        match res_snake {
-          FindContradictionResult::Contradiction => {
-            return Ok(item.with_move(pos, Field::Empty).with_filled(solution));
-          }
-          FindContradictionResult::Solved => {
-            return Ok(item.with_move(pos, Field::Snake).with_filled(solution));
-          }
-          FindContradictionResult::None => (),
-        }
-
-        let res_empty = {
-          let mut s = state.clone();
-          s.set(pos, Field::Empty);
-          find_contradiction(s, max_depth, pos)
-        };
-
-        match res_empty {
-          FindContradictionResult::Contradiction => {
-            return Ok(item.with_move(pos, Field::Snake).with_filled(solution));
-          }
-          FindContradictionResult::Solved => {
-            return Ok(item.with_move(pos, Field::Empty).with_filled(solution));
-          }
-          FindContradictionResult::None => (),
-        }
+      if let Some(step) = deduce2(state, pos, max_depth, budget) {
+        return Ok(item.with_step(step).with_filled(solution));
       }
     }
   }
@@ -339,14 +555,208 @@ fn further_item(item: Item, max_depth: usize, solution: &State) -> Result<Item,
   }
 }
 
+pub fn deduce(state: &State, pos: BoardVec, max_assume_depth: usize) -> Option<SolveStep> {
+  deduce2(state, pos, max_assume_depth, &Budget::unlimited())
+}
+
+pub fn deduce_within(
+  state: &State,
+  pos: BoardVec,
+  max_assume_depth: usize,
+  budget: &Budget,
+) -> Result<Option<SolveStep>, GaveUp> {
+  let step = deduce2(state, pos, max_assume_depth, budget);
+  budget.check(step)
+}
+
+fn deduce2(state: &State, pos: BoardVec, max_assume_depth: usize, budget: &Budget) -> Option<SolveStep> {
+  if state.field(pos) != Field::Unknown {
+    return None;
+  }
+
+  let snake_allowed = state.snake_allowed(pos);
+  let empty_allowed = state.empty_allowed(pos);
+  let field = match (snake_allowed, empty_allowed) {
+    (false, false) => return None,
+    (false, true) => Some(Field::Empty),
+    (true, false) => Some(Field::Snake),
+    (true, true) => None,
+  };
+
+  if let Some(field) = field {
+    return Some(SolveStep {
+      pos,
+      field,
+      deduction: Deduction::Obvious,
+    });
+  }
+
+  (1..=max_assume_depth).find_map(|depth| {
+    assume(state, pos, depth, budget).map(|field| SolveStep {
+      pos,
+      field,
+      deduction: Deduction::Assumption(depth),
+    })
+  })
+}
+
+fn assume(state: &State, pos: BoardVec, depth: usize, budget: &Budget) -> Option<Field> {
+  let res_snake = {
+    let mut s = state.clone();
+    s.set(pos, Field::Snake);
+    find_contradiction(s, depth, pos, budget)
+  };
+
+  match res_snake {
+    FindContradictionResult::Contradiction => return Some(Field::Empty),
+    FindContradictionResult::Solved => return Some(Field::Snake),
+    FindContradictionResult::None => (),
+  }
+
+  let res_empty = {
+    let mut s = state.clone();
+    s.set(pos, Field::Empty);
+    find_contradiction(s, depth, pos, budget)
+  };
+
+  match res_empty {
+    FindContradictionResult::Contradiction => Some(Field::Snake),
+    FindContradictionResult::Solved => Some(Field::Empty),
+    FindContradictionResult::None => None,
+  }
+}
+
+// Deduces the given moves in whatever order they become deducible,
+// fails with the first move that cannot be deduced anymore.
+pub fn replay_moves(
+  state: &State,
+  moves: &[BoardVec],
+  max_assume_depth: usize,
+) -> Result<(State, Vec<SolveStep>), BoardVec> {
+  replay_moves2(state, moves, max_assume_depth, &Budget::unlimited())
+}
+
+pub fn replay_moves_within(
+  state: &State,
+  moves: &[BoardVec],
+  max_assume_depth: usize,
+  budget: &Budget,
+) -> Result<Result<(State, Vec<SolveStep>), BoardVec>, GaveUp> {
+  let replayed = replay_moves2(state, moves, max_assume_depth, budget);
+  budget.check(replayed)
+}
+
+fn replay_moves2(
+  state: &State,
+  moves: &[BoardVec],
+  max_assume_depth: usize,
+  budget: &Budget,
+) -> Result<(State, Vec<SolveStep>), BoardVec> {
+  let mut state = state.clone();
+  let mut pending = moves.to_vec();
+  let mut steps = Vec::with_capacity(moves.len());
+
+  while let Some(&first) = pending.first() {
+    let before = pending.len();
+    pending.retain(|&pos| match deduce2(&state, pos, max_assume_depth, budget) {
+      Some(step) => {
+        state.set(pos, step.field);
+        steps.push(step);
+        false
+      }
+      None => true,
+    });
+
+    if pending.len() == before {
+      return Err(first);
+    }
+  }
+
+  Ok((state, steps))
+}
+
+// Solves `state` by always making the simplest deduction, the way `next_hint` picks it: a field that follows
+// without assumption if there is one, otherwise one that needs the lowest assumption depth, the first in board order
+// each time. The trace only depends on the puzzle, so ratings based on it do as well. Fails with the first unknown
+// field if no deduction is possible anymore.
+pub fn canonical_trace(state: &State, max_assume_depth: usize) -> Result<(State, Vec<SolveStep>), BoardVec> {
+  canonical_trace2(state, max_assume_depth, &Budget::unlimited())
+}
+
+pub fn canonical_trace_within(
+  state: &State,
+  max_assume_depth: usize,
+  budget: &Budget,
+) -> Result<Result<(State, Vec<SolveStep>), BoardVec>, GaveUp> {
+  let trace = canonical_trace2(state, max_assume_depth, budget);
+  budget.check(trace)
+}
+
+fn canonical_trace2(
+  state: &State,
+  max_assume_depth: usize,
+  budget: &Budget,
+) -> Result<(State, Vec<SolveStep>), BoardVec> {
+  let mut state = state.clone();
+  let mut steps = Vec::new();
+
+  loop {
+    let unknowns: Vec<BoardVec> = state
+      .board
+      .positions()
+      .filter(|&pos| state.field(pos) == Field::Unknown)
+      .collect();
+    let Some(&first) = unknowns.first() else {
+      return Ok((state, steps));
+    };
+
+    let step = (0..=max_assume_depth).find_map(|depth| {
+      unknowns
+        .iter()
+        .find_map(|&pos| deduce_exactly(&state, pos, depth, budget))
+    });
+    let Some(step) = step else {
+      return Err(first);
+    };
+    state.set(step.pos, step.field);
+    steps.push(step);
+  }
+}
+
+// Like `deduce2`, but only with exactly the given assumption depth, 0 meaning without assumption
+fn deduce_exactly(state: &State, pos: BoardVec, depth: usize, budget: &Budget) -> Option<SolveStep> {
+  if depth == 0 {
+    return deduce2(state, pos, 0, budget);
+  }
+  if state.field(pos) != Field::Unknown || !state.snake_allowed(pos) || !state.empty_allowed(pos) {
+    return None;
+  }
+
+  assume(state, pos, depth, budget).map(|field| SolveStep {
+    pos,
+    field,
+    deduction: Deduction::Assumption(depth),
+  })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum FindContradictionResult {
+pub(crate) enum FindContradictionResult {
   Contradiction,
   Solved,
   None,
 }
 
-fn find_contradiction(state: State, rest_depth: usize, last_pos: BoardVec) -> FindContradictionResult {
+// An exhausted budget makes the search inconclusive.
+pub(crate) fn find_contradiction(
+  state: State,
+  rest_depth: usize,
+  last_pos: BoardVec,
+  budget: &Budget,
+) -> FindContradictionResult {
+  if !budget.spend() {
+    return FindContradictionResult::None;
+  }
+
   //println!("{:?}", state);
   let state = match fill_obvious(state, &mut Throwaway) {
     FillResult::Contradiction => return FindContradictionResult::Contradiction,
@@ -363,7 +773,7 @@ fn find_contradiction(state: State, rest_depth: usize, last_pos: BoardVec) -> Fi
       let res_snake = {
         let mut s = state.clone();
         s.set(pos, Field::Snake);
-        find_contradiction(s, rest_depth - 1, pos)
+        find_contradiction(s, rest_depth - 1, pos, budget)
       };
 
       if res_snake == FindContradictionResult::Solved {
@@ -375,7 +785,7 @@ fn find_contradiction(state: State, rest_depth: usize, last_pos: BoardVec) -> Fi
       let res_empty = {
         let mut s = state.clone();
         s.set(pos, Field::Empty);
-        find_contradiction(s, rest_depth - 1, pos)
+        find_contradiction(s, rest_depth - 1, pos, budget)
       };
 
       match (res_snake, res_empty) {
@@ -390,3 +800,100 @@ fn find_contradiction(state: State, rest_depth: usize, last_pos: BoardVec) -> Fi
 
   FindContradictionResult::None
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use super::{count_solutions, count_solutions_within, find_solution_trace, solve};
+  use crate::board::BoardVec;
+  use crate::budget::{Budget, CancelToken, GaveUp};
+  use crate::progress::SearchProgress;
+  use crate::test_util::example_level;
+  use crate::{EmptyPolicy, State};
+
+  #[test]
+  fn test_count_solutions() {
+    let data = example_level();
+    let count = count_solutions(data.puzzle().unwrap(), 2);
+    assert!(count.is_unique());
+    assert!(count.nodes >= 1);
+
+    let state = State::new(5, 5, BoardVec::new(0, 0), BoardVec::new(0, 4), EmptyPolicy::None);
+    let mut results = Vec::new();
+    solve(state.clone(), &mut results, usize::MAX);
+    let all = count_solutions(state.clone(), usize::MAX);
+    assert!(all.exhausted);
+    assert_eq!(all.solutions, results.len());
+
+    let capped = count_solutions(state, 3);
+    assert_eq!(capped.solutions, 3);
+    assert!(!capped.exhausted);
+    assert!(capped.nodes < all.nodes);
+    assert!(capped.max_depth <= all.max_depth);
+  }
+
+  #[test]
+  fn test_budget() {
+    let state = State::new(
+      6,
+      6,
+      BoardVec::new(0, 0),
+      BoardVec::new(5, 5),
+      EmptyPolicy::new_ascending(6, 6),
+    );
+    let budget = Budget::unlimited().with_max_nodes(10);
+    assert_eq!(
+      count_solutions_within(state.clone(), 2, &budget),
+      Err(GaveUp::NodeLimit)
+    );
+    assert_eq!(budget.nodes(), 11);
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let budget = Budget::unlimited().with_cancel(cancel);
+    assert_eq!(count_solutions_within(state, 2, &budget), Err(GaveUp::Cancelled));
+
+    let data = example_level();
+    let (solution, _) = data.states().unwrap();
+    let begin = State::new(
+      7,
+      7,
+      solution.snake_ends()[0],
+      solution.snake_ends()[1],
+      data.empty_policy().unwrap(),
+    );
+    let budget = Budget::unlimited().with_max_nodes(5);
+    assert!(matches!(
+      find_solution_trace(begin, &solution, 1, None, &budget),
+      Err(GaveUp::NodeLimit)
+    ));
+  }
+
+  #[test]
+  fn test_progress() {
+    let data = example_level();
+    let (solution, _) = data.states().unwrap();
+    let begin = State::new(
+      7,
+      7,
+      solution.snake_ends()[0],
+      solution.snake_ends()[1],
+      data.empty_policy().unwrap(),
+    );
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let observer = {
+      let reports = reports.clone();
+      move |progress: &SearchProgress| reports.lock().unwrap().push(*progress)
+    };
+    let budget = Budget::unlimited()
+      .with_max_nodes(2000)
+      .with_observer(Arc::new(observer));
+    let _ = find_solution_trace(begin.clone(), &solution, 1, None, &budget);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports[0].queue, 1);
+    assert_eq!(reports[0].clues, 0);
+    assert_eq!(reports[0].best_unknowns, begin.unknowns);
+  }
+}
@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+use crate::board::BoardVec;
+use crate::{Field, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvgOptions {
+  // edge length of a field in pixels
+  pub cell_size: u32,
+  // draw the snake path and the shaded empty regions of the solution
+  pub solution: bool,
+}
+
+impl Default for SvgOptions {
+  fn default() -> Self {
+    Self {
+      cell_size: 40,
+      solution: false,
+    }
+  }
+}
+
+// Width and height of the image `render_svg` draws for a board
+pub fn svg_size(width: u32, height: u32, options: &SvgOptions) -> (u32, u32) {
+  ((width + 1) * options.cell_size, (height + 1) * options.cell_size)
+}
+
+// A standalone SVG image of the puzzle, with the solution drawn in if the options ask for it.
+pub fn render_svg(puzzle: &State, solution: &State, options: &SvgOptions) -> String {
+  let (width, height) = svg_size(puzzle.width(), puzzle.height(), options);
+  let mut out = String::new();
+  writeln!(
+    out,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+  )
+  .unwrap();
+  writeln!(out, r#"<rect width="{width}" height="{height}" fill="white"/>"#).unwrap();
+  let margin = options.cell_size as f64 / 2.0;
+  render_board(&mut out, puzzle, solution, options, margin, margin);
+  out.push_str("</svg>\n");
+  out
+}
+
+// Appends a group with the board whose top left corner is at (`x`, `y`), so several boards can share a page.
+pub fn render_board(out: &mut String, puzzle: &State, solution: &State, options: &SvgOptions, x: f64, y: f64) {
+  let cell = options.cell_size as f64;
+  let (width, height) = (puzzle.width() as f64 * cell, puzzle.height() as f64 * cell);
+  let center = |pos: BoardVec| (pos.x as f64 * cell + cell / 2.0, pos.y as f64 * cell + cell / 2.0);
+  writeln!(out, r#"<g transform="translate({x} {y})">"#).unwrap();
+
+  if options.solution {
+    for region in empty_regions(solution) {
+      let fill = region_color(region.len());
+      for &pos in region.iter() {
+        writeln!(
+          out,
+          r#"<rect x="{}" y="{}" width="{cell}" height="{cell}" fill="{fill}"/>"#,
+          pos.x as f64 * cell,
+          pos.y as f64 * cell
+        )
+        .unwrap();
+      }
+      let (cx, cy) = center(region[0]);
+      writeln!(
+        out,
+        r#"<text x="{cx}" y="{cy}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="dimgray">{}</text>"#,
+        cell * 0.4,
+        region.len()
+      )
+      .unwrap();
+    }
+  }
+
+  for i in 0..=puzzle.width() {
+    let lx = i as f64 * cell;
+    writeln!(
+      out,
+      r#"<line x1="{lx}" y1="0" x2="{lx}" y2="{height}" stroke="lightgray"/>"#
+    )
+    .unwrap();
+  }
+  for i in 0..=puzzle.height() {
+    let ly = i as f64 * cell;
+    writeln!(
+      out,
+      r#"<line x1="0" y1="{ly}" x2="{width}" y2="{ly}" stroke="lightgray"/>"#
+    )
+    .unwrap();
+  }
+  writeln!(
+    out,
+    r#"<rect width="{width}" height="{height}" fill="none" stroke="black" stroke-width="2"/>"#
+  )
+  .unwrap();
+
+  // clues: the snake as a block, empty fields as a dot
+  for pos in puzzle.board.positions() {
+    let (cx, cy) = center(pos);
+    match puzzle.field(pos) {
+      Field::Snake => writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="seagreen"/>"#,
+        cx - cell * 0.3,
+        cy - cell * 0.3,
+        cell * 0.6,
+        cell * 0.6,
+        cell * 0.1
+      )
+      .unwrap(),
+      Field::Empty => writeln!(out, r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="black"/>"#, cell * 0.1).unwrap(),
+      Field::SnakeEnd | Field::Unknown => {}
+    }
+  }
+
+  if options.solution {
+    let points: Vec<String> = snake_path(solution)
+      .into_iter()
+      .map(|pos| {
+        let (cx, cy) = center(pos);
+        format!("{cx},{cy}")
+      })
+      .collect();
+    writeln!(
+      out,
+      r#"<polyline points="{}" fill="none" stroke="seagreen" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round" opacity="0.8"/>"#,
+      points.join(" "),
+      cell * 0.3
+    )
+    .unwrap();
+  }
+
+  for &pos in puzzle.snake_ends() {
+    let (cx, cy) = center(pos);
+    writeln!(
+      out,
+      r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="darkgreen"/>"#,
+      cell * 0.35
+    )
+    .unwrap();
+  }
+
+  out.push_str("</g>\n");
+}
+
+// The fields of the snake from the first snake end to the second
+fn snake_path(solution: &State) -> Vec<BoardVec> {
+  let mut path: Vec<BoardVec> = solution.snake_ends().iter().take(1).copied().collect();
+  while let Some(&pos) = path.last() {
+    let previous = path.len().checked_sub(2).map(|i| path[i]);
+    let next = solution
+      .pos_around(pos)
+      .find(|&p| solution.field(p).is_snake() && Some(p) != previous);
+    match next {
+      Some(next) if path.len() == 1 || solution.field(pos) != Field::SnakeEnd => path.push(next),
+      _ => break,
+    }
+  }
+  path
+}
+
+// Connected groups of empty fields, each in board order
+fn empty_regions(solution: &State) -> Vec<Vec<BoardVec>> {
+  let mut regions: Vec<Vec<BoardVec>> = Vec::new();
+  for pos in solution.board.positions() {
+    if solution.field(pos) != Field::Empty || regions.iter().any(|region| region.contains(&pos)) {
+      continue;
+    }
+
+    let mut region = vec![pos];
+    let mut i = 0;
+    while i < region.len() {
+      for p in solution.pos_around(region[i]) {
+        if solution.field(p) == Field::Empty && !region.contains(&p) {
+          region.push(p);
+        }
+      }
+      i += 1;
+    }
+    region.sort_by_key(|p| (p.y, p.x));
+    regions.push(region);
+  }
+  regions
+}
+
+// Regions of the same size get the same pale color
+fn region_color(size: usize) -> String {
+  format!("hsl({}, 70%, 85%)", (size * 67) % 360)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{render_svg, snake_path, SvgOptions};
+  use crate::test_util::example_level;
+  use crate::Field;
+
+  #[test]
+  fn test_render_svg() {
+    let data = example_level();
+    let (solution, puzzle) = data.states().unwrap();
+
+    let path = snake_path(&solution);
+    assert_eq!(path.first(), solution.snake_ends().first());
+    assert_eq!(path.last(), solution.snake_ends().last());
+    assert_eq!(path.len(), solution.board.iter().filter(|f| f.is_snake()).count());
+
+    let svg = render_svg(&puzzle, &solution, &SvgOptions::default());
+    assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+    assert!(!svg.contains("polyline"));
+    let dots = puzzle.board.iter().filter(|&&f| f == Field::Empty).count();
+    assert_eq!(svg.matches("<circle").count(), dots + 2);
+
+    let svg = render_svg(
+      &puzzle,
+      &solution,
+      &SvgOptions {
+        solution: true,
+        ..SvgOptions::default()
+      },
+    );
+    assert!(svg.contains("polyline"));
+    assert!(svg.contains("<text"));
+  }
+}
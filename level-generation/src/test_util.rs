@@ -0,0 +1,8 @@
+use crate::serialize::LevelData;
+
+// The 7x7 example level with ascending empty regions and assumption depth 1, in the legacy format
+pub const LEVEL: &str = include_str!("../../assets/levels/level.json");
+
+pub fn example_level() -> LevelData {
+  LevelData::from_json(LEVEL).unwrap()
+}
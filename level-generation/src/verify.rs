@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::board::BoardVec;
+use crate::budget::{Budget, GaveUp};
+use crate::serialize::{LevelData, LevelError};
+use crate::{deduce_within, solve_within, Field};
+
+#[derive(Debug)]
+pub enum VerifyError {
+  Level(LevelError),
+  NoSolution,
+  MultipleSolutions,
+  SolutionMismatch,
+  MoveOnKnownField { index: usize, pos: BoardVec },
+  MoveNotDeducible { index: usize, pos: BoardVec },
+  MoveContradictsSolution { index: usize, pos: BoardVec, field: Field },
+  Incomplete { unknowns: u32 },
+}
+
+impl fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VerifyError::Level(err) => write!(f, "{err}"),
+      VerifyError::NoSolution => write!(f, "puzzle has no solution"),
+      VerifyError::MultipleSolutions => write!(f, "puzzle has more than one solution"),
+      VerifyError::SolutionMismatch => write!(f, "solution differs from the stored level"),
+      VerifyError::MoveOnKnownField { index, pos } => {
+        write!(f, "move {index} at {pos:?} targets a field that is already known")
+      }
+      VerifyError::MoveNotDeducible { index, pos } => {
+        write!(f, "move {index} at {pos:?} cannot be deduced")
+      }
+      VerifyError::MoveContradictsSolution { index, pos, field } => {
+        write!(
+          f,
+          "move {index} deduces {field:?} at {pos:?}, which contradicts the solution"
+        )
+      }
+      VerifyError::Incomplete { unknowns } => {
+        write!(f, "moves leave {unknowns} field(s) unknown")
+      }
+    }
+  }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<LevelError> for VerifyError {
+  fn from(err: LevelError) -> Self {
+    VerifyError::Level(err)
+  }
+}
+
+pub fn verify_level(level: &LevelData) -> Result<(), VerifyError> {
+  verify_level_within(level, &Budget::unlimited()).expect("an unlimited budget never runs out")
+}
+
+// Like `verify_level`, a level is only judged if the budget lasts.
+pub fn verify_level_within(level: &LevelData, budget: &Budget) -> Result<Result<(), VerifyError>, GaveUp> {
+  let verified = verify_level2(level, budget);
+  budget.check(verified)
+}
+
+// Stops with Ok once the budget is used up, `verify_level_within` reports that as `GaveUp` instead.
+fn verify_level2(level: &LevelData, budget: &Budget) -> Result<(), VerifyError> {
+  let (solution, puzzle) = level.states()?;
+
+  let mut results = Vec::new();
+  if solve_within(puzzle.clone(), &mut results, 2, budget).is_err() {
+    return Ok(());
+  }
+  match &results[..] {
+    [] => return Err(VerifyError::NoSolution),
+    [found] if found != &solution => return Err(VerifyError::SolutionMismatch),
+    [_] => (),
+    _ => return Err(VerifyError::MultipleSolutions),
+  }
+
+  if let Some(data) = level.move_data() {
+    for (index, m) in data.iter().enumerate() {
+      if m.field != solution.field(m.pos) {
+        return Err(VerifyError::MoveContradictsSolution {
+          index,
+          pos: m.pos,
+          field: m.field,
+        });
+      }
+    }
+  }
+
+  let mut state = puzzle;
+  for (index, &pos) in level.moves().iter().enumerate() {
+    if state.board.get(pos).is_none() {
+      return Err(LevelError::OutOfBounds(pos).into());
+    }
+    if state.field(pos) != Field::Unknown {
+      return Err(VerifyError::MoveOnKnownField { index, pos });
+    }
+
+    let Ok(step) = deduce_within(&state, pos, level.max_assumption_depth(), budget) else {
+      return Ok(());
+    };
+    let field = step.ok_or(VerifyError::MoveNotDeducible { index, pos })?.field;
+    if field != solution.field(pos) {
+      return Err(VerifyError::MoveContradictsSolution { index, pos, field });
+    }
+    state.set(pos, field);
+  }
+
+  if state.unknowns() > 0 {
+    return Err(VerifyError::Incomplete {
+      unknowns: state.unknowns(),
+    });
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::{verify_level, verify_level_within, VerifyError};
+  use crate::budget::{Budget, GaveUp};
+  use crate::serialize::LevelData;
+  use crate::test_util::{example_level, LEVEL};
+
+  #[test]
+  fn test_shipped_levels() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/levels");
+    for entry in fs::read_dir(dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.extension().is_some_and(|ext| ext == "json") {
+        let level = LevelData::load(&path).unwrap();
+        if let Err(err) = verify_level(&level) {
+          panic!("{}: {err}", path.display());
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_missing_move() {
+    // the file stores the last move first, so this drops the final move
+    let broken = LEVEL.replacen("[\n      6,\n      1\n    ],\n", "", 1);
+    let level = LevelData::from_json(&broken).unwrap();
+    assert!(matches!(
+      verify_level(&level),
+      Err(VerifyError::Incomplete { unknowns: 1 })
+    ));
+  }
+
+  #[test]
+  fn test_verify_budget() {
+    let level = example_level();
+    let budget = Budget::unlimited().with_max_nodes(5);
+    assert!(matches!(verify_level_within(&level, &budget), Err(GaveUp::NodeLimit)));
+    assert!(matches!(verify_level_within(&level, &Budget::unlimited()), Ok(Ok(()))));
+  }
+}
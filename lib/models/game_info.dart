@@ -19,6 +19,10 @@ abstract class EmptyPolicy {
   const EmptyPolicy();
 }
 
+class NoneEmptyPolicy extends EmptyPolicy {
+  const NoneEmptyPolicy();
+}
+
 class FixEmptyPolicy extends EmptyPolicy {
   final int fields;
 
@@ -31,12 +35,51 @@ class AscendingEmptyPolicy extends EmptyPolicy {
   const AscendingEmptyPolicy({required this.top});
 }
 
+class RangeEmptyPolicy extends EmptyPolicy {
+  final int min;
+  final int max;
+
+  const RangeEmptyPolicy({required this.min, required this.max});
+}
+
+class SizesEmptyPolicy extends EmptyPolicy {
+  // Every entry can be used by one region, sizes may repeat
+  final List<int> sizes;
+
+  const SizesEmptyPolicy({required this.sizes});
+}
+
+class DistinctEmptyPolicy extends EmptyPolicy {
+  const DistinctEmptyPolicy();
+}
+
+class ParityEmptyPolicy extends EmptyPolicy {
+  final bool odd;
+
+  const ParityEmptyPolicy({required this.odd});
+}
+
+class SolveMove {
+  final BoardVec pos;
+
+  // Only known for levels of format version 2 and later
+  final Field? field;
+  final String? rule;
+  final Map<String, dynamic> ruleArgs;
+
+  const SolveMove(this.pos, {this.field, this.rule, this.ruleArgs = const {}});
+}
+
 class GameInfo {
+  static const int supportedVersion = 2;
+
   final Board<Field> solution;
   final List<BoardVec> initialOpen;
-  final List<BoardVec> solveMoves;
+  // In solve order
+  final List<SolveMove> solveMoves;
   final EmptyPolicy emptyPolicy;
   final int maxAssumptionDepth;
+  final int? difficultyScore;
 
   int get width => solution.width;
   int get height => solution.height;
@@ -47,9 +90,15 @@ class GameInfo {
     required this.initialOpen,
     required this.solveMoves,
     required this.emptyPolicy,
+    this.difficultyScore,
   });
 
   static GameInfo loadFromJson(Map<String, dynamic> json) {
+    int version = json["version"] ?? 1;
+    if (version > supportedVersion) {
+      throw Exception("Unsupported level format version $version, at most $supportedVersion is supported");
+    }
+
     int width = json["width"];
     int height = json["height"];
     int maxAssumptionDepth = json["max_assumption_depth"];
@@ -90,22 +139,62 @@ class GameInfo {
     }
 
     final initialOpen = (json["initial_open"] as List<dynamic>).map(toVec).toList(growable: false);
-    final solveMoves = (json["moves"] as List<dynamic>).map(toVec).toList(growable: false);
+    SolveMove toMove(dynamic v) {
+      final move = v as Map<String, dynamic>;
+      final field = switch (move["field"]) {
+        "Snake" => Field.snake,
+        "Empty" => Field.empty,
+        final other => throw Exception("Unknown move field $other"),
+      };
+      final rule = move["rule"];
+      if (rule is Map<String, dynamic>) {
+        return SolveMove(toVec(move["pos"]), field: field, rule: rule.keys.single, ruleArgs: rule.values.single);
+      }
+      return SolveMove(toVec(move["pos"]), field: field, rule: rule as String);
+    }
+
+    final moves = json["moves"] as List<dynamic>;
+    final solveMoves = version >= 2
+        ? moves.map(toMove).toList(growable: false)
+        // Version 1 stores the last move first
+        : moves.reversed.map((v) => SolveMove(toVec(v))).toList(growable: false);
 
     EmptyPolicy? emptyPolicy;
     final emptyPolicyRoot = json["empty_policy"];
-    final ascending = emptyPolicyRoot["Ascending"];
 
-    if (ascending != null) {
-      emptyPolicy = AscendingEmptyPolicy(top: ascending["top"] as int);
+    if (emptyPolicyRoot == "None") {
+      emptyPolicy = const NoneEmptyPolicy();
+    } else if (emptyPolicyRoot == "Distinct") {
+      emptyPolicy = const DistinctEmptyPolicy();
+    } else if (emptyPolicyRoot == "Odd" || emptyPolicyRoot == "Even") {
+      emptyPolicy = ParityEmptyPolicy(odd: emptyPolicyRoot == "Odd");
+    } else if (emptyPolicyRoot is Map<String, dynamic>) {
+      final ascending = emptyPolicyRoot["Ascending"];
+      final fix = emptyPolicyRoot["Fix"];
+      final range = emptyPolicyRoot["Range"];
+      final sizes = emptyPolicyRoot["Sizes"];
+
+      if (ascending != null) {
+        emptyPolicy = AscendingEmptyPolicy(top: ascending["top"] as int);
+      } else if (fix != null) {
+        emptyPolicy = FixEmptyPolicy(fields: fix["fix_size"] as int);
+      } else if (range != null) {
+        emptyPolicy = RangeEmptyPolicy(min: range["min"] as int, max: range["max"] as int);
+      } else if (sizes != null) {
+        emptyPolicy = SizesEmptyPolicy(sizes: (sizes["sizes"] as List<dynamic>).cast<int>().toList(growable: false));
+      }
     }
 
     if (emptyPolicy == null) {
       throw Exception("No empty policy in level data");
     }
 
+    final difficulty = json["difficulty"];
+    final difficultyScore = difficulty != null ? difficulty["score"] as int : null;
+
     return GameInfo(
       maxAssumptionDepth: maxAssumptionDepth,
+      difficultyScore: difficultyScore,
       solution: solution,
       initialOpen: initialOpen,
       solveMoves: solveMoves,
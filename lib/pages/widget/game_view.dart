@@ -21,6 +21,16 @@ abstract class _EmptyPolicyTracker {
       return _FixedEmptyPolicyTracker(ep.fields);
     } else if (ep is AscendingEmptyPolicy) {
       return _AscendingEmptyPolicyTracker(ep.top);
+    } else if (ep is RangeEmptyPolicy) {
+      return _RangeEmptyPolicyTracker(ep.min, ep.max);
+    } else if (ep is SizesEmptyPolicy) {
+      return _SizesEmptyPolicyTracker(ep.sizes);
+    } else if (ep is DistinctEmptyPolicy) {
+      return _DistinctEmptyPolicyTracker();
+    } else if (ep is ParityEmptyPolicy) {
+      return _ParityEmptyPolicyTracker(ep.odd);
+    } else if (ep is NoneEmptyPolicy) {
+      return _NoneEmptyPolicyTracker();
     }
     throw Exception("Unknown empty policy!");
   }
@@ -91,8 +101,139 @@ class _FixedEmptyPolicyTracker extends _EmptyPolicyTracker {
 
   @override
   Widget info(BuildContext context) {
-    // TODO: implement info
-    throw UnimplementedError();
+    return Text("Connected empty fields should have size $fields");
+  }
+}
+
+class _RangeEmptyPolicyTracker extends _EmptyPolicyTracker {
+  final int min;
+  final int max;
+
+  _RangeEmptyPolicyTracker(this.min, this.max);
+
+  @override
+  bool isOk(int num) => num >= min && num <= max;
+
+  @override
+  void notifyNewEnclosedEmpties(int num) {}
+
+  @override
+  void notifyRemoveEnclosedEmpties(int num) {}
+
+  @override
+  Widget info(BuildContext context) {
+    return Text("Connected empty fields should have size $min to $max");
+  }
+}
+
+class _SizesEmptyPolicyTracker extends _EmptyPolicyTracker {
+  final List<int> sizes;
+  final ObservableMap<int, int> used;
+
+  _SizesEmptyPolicyTracker(List<int> sizes)
+      : sizes = (List.of(sizes)..sort()),
+        used = ObservableMap();
+
+  int _allowed(int num) => sizes.where((size) => size == num).length;
+
+  @override
+  bool isOk(int num) => _allowed(num) > 0 && (used[num] ?? 0) <= _allowed(num);
+
+  @override
+  void notifyNewEnclosedEmpties(int num) {
+    used[num] = (used[num] ?? 0) + 1;
+  }
+
+  @override
+  void notifyRemoveEnclosedEmpties(int num) {
+    assert((used[num] ?? 0) > 0);
+    used[num] = used[num]! - 1;
+  }
+
+  @override
+  Widget info(BuildContext context) {
+    return Column(
+      children: [
+        const Text("Connected empty fields should have the sizes:"),
+        Observer(builder: (context) {
+          return Row(
+            mainAxisSize: MainAxisSize.min,
+            children: List.generate(
+              sizes.length,
+              (i) {
+                // Repeated sizes are struck through from the left, one per region using them
+                final earlier = sizes.take(i).where((size) => size == sizes[i]).length;
+                final style =
+                    earlier < (used[sizes[i]] ?? 0) ? const TextStyle(decoration: TextDecoration.lineThrough) : null;
+                return Padding(
+                  padding: const EdgeInsets.symmetric(horizontal: 4),
+                  child: Text.rich(TextSpan(text: "${sizes[i]}", style: style)),
+                );
+              },
+            ),
+          );
+        }),
+      ],
+    );
+  }
+}
+
+class _DistinctEmptyPolicyTracker extends _EmptyPolicyTracker {
+  final Map<int, int> used = {};
+
+  @override
+  bool isOk(int num) => (used[num] ?? 0) <= 1;
+
+  @override
+  void notifyNewEnclosedEmpties(int num) {
+    used[num] = (used[num] ?? 0) + 1;
+  }
+
+  @override
+  void notifyRemoveEnclosedEmpties(int num) {
+    assert((used[num] ?? 0) > 0);
+    used[num] = used[num]! - 1;
+  }
+
+  @override
+  Widget info(BuildContext context) {
+    return const Text("Connected empty fields should all have different sizes");
+  }
+}
+
+class _ParityEmptyPolicyTracker extends _EmptyPolicyTracker {
+  final bool odd;
+
+  _ParityEmptyPolicyTracker(this.odd);
+
+  @override
+  bool isOk(int num) => num.isOdd == odd;
+
+  @override
+  void notifyNewEnclosedEmpties(int num) {}
+
+  @override
+  void notifyRemoveEnclosedEmpties(int num) {}
+
+  @override
+  Widget info(BuildContext context) {
+    return Text("Connected empty fields should have ${odd ? "an odd" : "an even"} size");
+  }
+}
+
+class _NoneEmptyPolicyTracker extends _EmptyPolicyTracker {
+  @override
+  bool isOk(int num) => true;
+
+  @override
+  void notifyNewEnclosedEmpties(int num) {}
+
+  @override
+  void notifyRemoveEnclosedEmpties(int num) {}
+
+  @override
+  Widget info(BuildContext context) {
+    return const Text("Connected empty fields can have any size");
   }
 }
 
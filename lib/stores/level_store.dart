@@ -27,6 +27,13 @@ class LevelStore {
       levels.add(gameInfo);
     }
 
+    // Order by difficulty, levels without rating keep their manifest order in front
+    final order = {for (final (i, level) in levels.indexed) level: i};
+    levels.sort((a, b) {
+      final byScore = (a.difficultyScore ?? -1).compareTo(b.difficultyScore ?? -1);
+      return byScore != 0 ? byScore : order[a]!.compareTo(order[b]!);
+    });
+
     return LevelStore._(List.unmodifiable(levels));
   }
 }